@@ -1,5 +1,7 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader, Lines};
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::num::{NonZeroUsize, ParseIntError};
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Direction {
@@ -7,34 +9,50 @@ enum Direction {
     Right,
 }
 
-const MAX: i16 = 100;
+// Positions and rotations
+type Value = i64;
+// Intermediate positions and counts, can't overflow for any Value
+type Total = i128;
+
 impl Direction {
-    fn shift(self, value: i16) -> i16 {
+    // Mirrors left rotations so that both directions count up towards size
+    fn shift(self, value: Total, size: Total) -> Total {
         match self {
-            Direction::Left => MAX - value,
+            Direction::Left => size - value,
             Direction::Right => value,
         }
     }
 }
 
-impl From<&str> for Direction {
-    fn from(c: &str) -> Self {
+impl TryFrom<&str> for Direction {
+    type Error = ParseErrorKind;
+
+    fn try_from(c: &str) -> Result<Self, Self::Error> {
         match c {
-            "L" => Direction::Left,
-            "R" => Direction::Right,
-            _ => panic!("Invalid direction: {c}"),
+            "L" | "-" => Ok(Direction::Left),
+            "R" | "+" => Ok(Direction::Right),
+            _ => Err(ParseErrorKind::Direction),
         }
     }
 }
 
-#[derive(Debug)]
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Direction::Left => write!(f, "L"),
+            Direction::Right => write!(f, "R"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 struct Instruction {
     direction: Direction,
-    value: i16,
+    value: Value,
 }
 
 impl Instruction {
-    fn new(direction: Direction, value: i16) -> Self {
+    fn new(direction: Direction, value: Value) -> Self {
         if value <= 0 {
             panic!("value = {value} must be >= 0");
         }
@@ -42,202 +60,943 @@ impl Instruction {
     }
 }
 
-type FileLines = Lines<BufReader<File>>;
-fn parse(lines: FileLines) -> impl Iterator<Item = Instruction> {
-    lines.into_iter().map(|line| {
-        let line = line.expect("line is valid");
-        Instruction::new(
-            Direction::from(&line[0..1]),
-            line[1..].parse().expect("line ends with valid i16"),
+#[derive(Debug, PartialEq)]
+enum ParseErrorKind {
+    Empty,
+    Direction,
+    Value(ParseIntError),
+    NotPositive,
+    Repeat(ParseIntError),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::Empty => write!(f, "missing instruction"),
+            ParseErrorKind::Direction => write!(f, "direction must be 'L', 'R', '-' or '+'"),
+            ParseErrorKind::Value(e) => write!(f, "invalid value ({e})"),
+            ParseErrorKind::NotPositive => write!(f, "value must be > 0"),
+            ParseErrorKind::Repeat(e) => write!(f, "invalid repeat count ({e})"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct ParseError {
+    // 1-based, in characters for the column
+    line: usize,
+    column: usize,
+    text: String,
+    kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}: {:?}",
+            self.line, self.column, self.kind, self.text
         )
-    })
+    }
 }
 
-fn solve(mut dial: i16, instructions: impl Iterator<Item = Instruction>) -> i16 {
-    let mut count = 0;
-    for instruction in instructions {
-        let mut next = instruction.direction.shift(dial) + instruction.value;
-        count += ((next as f64 / MAX as f64).floor() as i16).abs();
-        if instruction.direction == Direction::Left && dial == 0 {
-            count -= 1;
-        }
-        next = next.rem_euclid(MAX);
-        if next != 0 {
-            dial = instruction.direction.shift(next);
+impl std::error::Error for ParseError {}
+
+fn error(column: usize, text: &str, kind: ParseErrorKind) -> ParseError {
+    ParseError {
+        line: 1,
+        column,
+        text: text.to_string(),
+        kind,
+    }
+}
+
+// E.g. "L50", "R5" or "-50", "+5", column is where the token starts
+fn parse_instruction(token: &str, column: usize) -> Result<Instruction, ParseError> {
+    let Some(first) = token.chars().next() else {
+        return Err(error(column, token, ParseErrorKind::Empty));
+    };
+    let (direction, value) = token.split_at(first.len_utf8());
+    let direction =
+        Direction::try_from(direction).map_err(|kind| error(column, direction, kind))?;
+    let parsed = value
+        .parse()
+        .map_err(|e| error(column + 1, value, ParseErrorKind::Value(e)))?;
+    if parsed <= 0 {
+        return Err(error(column + 1, value, ParseErrorKind::NotPositive));
+    }
+    Ok(Instruction::new(direction, parsed))
+}
+
+// Parses a single instruction, errors are reported on line 1
+impl FromStr for Instruction {
+    type Err = ParseError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let trimmed = line.trim_start();
+        let column = line[..line.len() - trimmed.len()].chars().count() + 1;
+        parse_instruction(trimmed.trim_end(), column)
+    }
+}
+
+// Comma and/or whitespace separated items with their offset in characters
+fn split_items(list: &str) -> impl Iterator<Item = (usize, &str)> {
+    list.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+        .map(move |item| {
+            let offset = item.as_ptr() as usize - list.as_ptr() as usize;
+            (list[..offset].chars().count(), item)
+        })
+}
+
+// Instructions with their repeat count, e.g. "R50x3, -5 L2 # comment", errors are reported on
+// line 1. Lines with only a comment are valid.
+fn parse_line(line: &str) -> Result<Vec<(Instruction, usize)>, ParseError> {
+    let (line, comment) = match line.split_once('#') {
+        Some((line, _)) => (line, true),
+        None => (line, false),
+    };
+    let instructions = split_items(line)
+        .map(|(offset, item)| {
+            let column = offset + 1;
+            let Some((token, repeat)) = item.rsplit_once('x') else {
+                return Ok((parse_instruction(item, column)?, 1));
+            };
+            let instruction = parse_instruction(token, column)?;
+            let repeat = repeat.parse::<NonZeroUsize>().map_err(|e| {
+                error(
+                    column + token.chars().count() + 1,
+                    repeat,
+                    ParseErrorKind::Repeat(e),
+                )
+            })?;
+            Ok((instruction, repeat.get()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if instructions.is_empty() && !comment {
+        return Err(error(1, "", ParseErrorKind::Empty));
+    }
+    Ok(instructions)
+}
+
+// Optional first line of the input, e.g. "markers: 0, 25, 50"
+const MARKERS_HEADER: &str = "markers:";
+
+// Comma and/or whitespace separated positions, column is where list starts
+fn parse_markers(list: &str, column: usize) -> Result<Vec<Value>, ParseError> {
+    split_items(list)
+        .map(|(offset, item)| {
+            item.parse()
+                .map_err(|e| error(column + offset, item, ParseErrorKind::Value(e)))
+        })
+        .collect()
+}
+
+fn parse_header(data: &str) -> Option<Result<Vec<Value>, ParseError>> {
+    let list = data.lines().next()?.strip_prefix(MARKERS_HEADER)?;
+    Some(parse_markers(list, MARKERS_HEADER.len() + 1))
+}
+
+// Lenient parsing skips blank lines, the markers header is always skipped
+fn parse(
+    reader: impl BufRead,
+    lenient: bool,
+) -> impl Iterator<Item = Result<Instruction, ParseError>> {
+    reader
+        .lines()
+        .enumerate()
+        .map(|(ind, line)| (ind + 1, line.expect("line is valid")))
+        .filter(move |(ind, line)| {
+            if *ind == 1 && line.starts_with(MARKERS_HEADER) {
+                return false;
+            }
+            !lenient || !line.trim().is_empty()
+        })
+        .flat_map(|(ind, line)| {
+            let (instructions, error) = match parse_line(&line) {
+                Ok(instructions) => (instructions, None),
+                Err(error) => (Vec::new(), Some(ParseError { line: ind, ..error })),
+            };
+            instructions
+                .into_iter()
+                .flat_map(|(instruction, repeat)| std::iter::repeat_n(instruction, repeat))
+                .map(Ok)
+                .chain(error.map(Err))
+        })
+}
+
+const SIZE: Value = 100;
+const START: Value = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Dial {
+    size: Value,
+    position: Value,
+}
+
+impl Dial {
+    fn new(size: Value, position: Value) -> Self {
+        if size <= 0 {
+            panic!("size = {size} must be > 0");
+        }
+        if !(0..size).contains(&position) {
+            panic!("position = {position} must be in [0, {size})");
+        }
+        Self { size, position }
+    }
+
+    // Returns the number of clicks going through 0
+    fn rotate(&mut self, instruction: &Instruction) -> Total {
+        let size = self.size as Total;
+        let direction = instruction.direction;
+        // > 0 since shift(position) is in [0, size] and value > 0
+        let mut next = direction.shift(self.position as Total, size) + instruction.value as Total;
+        let mut passed = next / size;
+        if direction == Direction::Left && self.position == 0 {
+            passed -= 1;
+        }
+        next %= size;
+        self.position = if next != 0 {
+            direction.shift(next, size) as Value
         } else {
-            dial = 0;
+            0
+        };
+        passed
+    }
+
+    fn step(&mut self, instruction: &Instruction) -> Step {
+        let before = self.position;
+        let raw = match instruction.direction {
+            Direction::Left => before as Total - instruction.value as Total,
+            Direction::Right => before as Total + instruction.value as Total,
+        };
+        let passed = self.rotate(instruction);
+        Step {
+            before,
+            raw,
+            after: self.position,
+            passed,
+        }
+    }
+}
+
+// What a single instruction did to the dial
+#[derive(Debug, PartialEq)]
+struct Step {
+    before: Value,
+    // Position if the dial didn't wrap around
+    raw: Total,
+    after: Value,
+    passed: Total,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Csv,
+    Json,
+}
+
+impl From<&str> for Format {
+    fn from(format: &str) -> Self {
+        match format {
+            "csv" => Format::Csv,
+            "json" => Format::Json,
+            _ => panic!("Invalid format: {format}"),
         }
     }
+}
+
+// One line per instruction, JSON is written as JSON lines
+fn trace(
+    mut dial: Dial,
+    instructions: impl Iterator<Item = Instruction>,
+    format: Format,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    if format == Format::Csv {
+        writeln!(out, "index,direction,value,before,raw,after,passed")?;
+    }
+    for (index, instruction) in instructions.enumerate() {
+        let Step {
+            before,
+            raw,
+            after,
+            passed,
+        } = dial.step(&instruction);
+        let Instruction { direction, value } = instruction;
+        match format {
+            Format::Csv => writeln!(
+                out,
+                "{index},{direction},{value},{before},{raw},{after},{passed}"
+            )?,
+            Format::Json => writeln!(
+                out,
+                concat!(
+                    "{{\"index\":{index},\"direction\":\"{direction}\",\"value\":{value},",
+                    "\"before\":{before},\"raw\":{raw},\"after\":{after},\"passed\":{passed}}}"
+                ),
+                index = index,
+                direction = direction,
+                value = value,
+                before = before,
+                raw = raw,
+                after = after,
+                passed = passed,
+            )?,
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Count {
+    // Part 1: instructions leaving the dial on 0
+    landed: Total,
+    // Part 2: clicks going through 0
+    passed: Total,
+}
+
+impl Count {
+    fn get(&self, part: Part) -> Total {
+        match part {
+            Part::One => self.landed,
+            Part::Two => self.passed,
+        }
+    }
+
+    fn add(&mut self, dial: &mut Dial, instruction: &Instruction) {
+        self.passed += dial.rotate(instruction);
+        if dial.position == 0 {
+            self.landed += 1;
+        }
+    }
+}
+
+fn solve(mut dial: Dial, instructions: impl Iterator<Item = Instruction>) -> Count {
+    let mut count = Count::default();
+    for instruction in instructions {
+        count.add(&mut dial, &instruction);
+    }
 
     count
 }
 
-fn main() {
-    println!(
-        "{}",
-        solve(
-            50,
-            parse(
-                BufReader::new(
-                    File::open(std::env::args().nth(1).expect("filename")).expect("file exists"),
-                )
-                .lines()
-            )
+// Counts for each marker, marker m is handled as 0 on a dial turned by -m
+fn solve_markers(
+    dial: Dial,
+    markers: &[Value],
+    instructions: impl Iterator<Item = Instruction>,
+) -> Vec<Count> {
+    let mut dials = markers
+        .iter()
+        .map(|marker| Dial::new(dial.size, (dial.position - marker).rem_euclid(dial.size)))
+        .collect::<Vec<_>>();
+    let mut counts = markers.iter().map(|_| Count::default()).collect::<Vec<_>>();
+    for instruction in instructions {
+        for (dial, count) in dials.iter_mut().zip(&mut counts) {
+            count.add(dial, &instruction);
+        }
+    }
+
+    counts
+}
+
+// Instructions simulated over all start positions by invert, a few seconds of work
+const MAX_INVERT_STEPS: Total = 1_000_000_000;
+
+// Start positions for which the count of part matches, optionally also ending on end, in
+// ascending order as they are found
+fn invert(
+    size: Value,
+    instructions: &[Instruction],
+    part: Part,
+    count: Total,
+    end: Option<Value>,
+) -> impl Iterator<Item = Value> + '_ {
+    (0..size).filter(move |&start| {
+        let mut dial = Dial::new(size, start);
+        let mut out = Count::default();
+        for instruction in instructions {
+            out.add(&mut dial, instruction);
+        }
+        out.get(part) == count && end.is_none_or(|end| dial.position == end)
+    })
+}
+
+// Stats keep counters for every position, larger dials would take gigabytes
+const MAX_STATS_SIZE: Value = 10_000_000;
+
+#[derive(Debug, PartialEq)]
+struct Stats {
+    // Full turns, every position was passed once per lap
+    laps: Total,
+    // Difference array of the remaining passes, has size + 1 items
+    partial: Vec<Total>,
+    landed: Vec<Total>,
+    // Instruction index and clicks going through 0
+    zero_events: Vec<(usize, Total)>,
+    // Instructions in a row not going through 0
+    longest_streak: usize,
+    streak: usize,
+}
+
+impl Stats {
+    // size must be <= MAX_STATS_SIZE
+    fn new(size: Value) -> Self {
+        assert!(
+            size <= MAX_STATS_SIZE,
+            "size = {size} is too large for stats"
+        );
+        Self {
+            laps: 0,
+            partial: vec![0; size as usize + 1],
+            landed: vec![0; size as usize],
+            zero_events: Vec::new(),
+            longest_streak: 0,
+            streak: 0,
+        }
+    }
+
+    fn add(&mut self, index: usize, step: &Step) {
+        let size = self.landed.len();
+        // Clicks passed through positions [first, first + clicks) before wrapping
+        let first = if step.raw > step.before as Total {
+            step.before as Total + 1
+        } else {
+            step.raw
+        };
+        let clicks = (step.raw - step.before as Total).abs();
+        self.laps += clicks / size as Total;
+        let start = first.rem_euclid(size as Total) as usize;
+        let end = start + (clicks % size as Total) as usize;
+        self.partial[start] += 1;
+        if end > size {
+            self.partial[size] -= 1;
+            self.partial[0] += 1;
+            self.partial[end - size] -= 1;
+        } else {
+            self.partial[end] -= 1;
+        }
+        self.landed[step.after as usize] += 1;
+
+        if step.passed > 0 {
+            self.zero_events.push((index, step.passed));
+            self.streak = 0;
+        } else {
+            self.streak += 1;
+            self.longest_streak = std::cmp::max(self.longest_streak, self.streak);
+        }
+    }
+
+    fn passed(&self) -> Vec<Total> {
+        self.partial[..self.landed.len()]
+            .iter()
+            .scan(self.laps, |passed, diff| {
+                *passed += diff;
+                Some(*passed)
+            })
+            .collect()
+    }
+
+    fn write(&self, format: Format, out: &mut impl Write) -> std::io::Result<()> {
+        let positions = self.passed().into_iter().zip(&self.landed).enumerate();
+        match format {
+            // One table, kind tells which columns are set: index is the position or the
+            // instruction index of a zero event
+            Format::Csv => {
+                writeln!(out, "kind,index,passed,landed,length")?;
+                for (position, (passed, landed)) in positions {
+                    writeln!(out, "position,{position},{passed},{landed},")?;
+                }
+                for (index, passed) in &self.zero_events {
+                    writeln!(out, "zero_event,{index},{passed},,")?;
+                }
+                writeln!(out, "longest_streak,,,,{}", self.longest_streak)?;
+            }
+            Format::Json => {
+                let positions = positions
+                    .map(|(position, (passed, landed))| {
+                        format!(
+                            "{{\"position\":{position},\"passed\":{passed},\"landed\":{landed}}}"
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let zero_events = self
+                    .zero_events
+                    .iter()
+                    .map(|(index, passed)| format!("{{\"index\":{index},\"passed\":{passed}}}"))
+                    .collect::<Vec<_>>();
+                writeln!(
+                    out,
+                    "{{\"positions\":[{}],\"longest_streak\":{},\"zero_events\":[{}]}}",
+                    positions.join(","),
+                    self.longest_streak,
+                    zero_events.join(",")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn stats(mut dial: Dial, instructions: impl Iterator<Item = Instruction>) -> Stats {
+    let mut stats = Stats::new(dial.size);
+    for (index, instruction) in instructions.enumerate() {
+        stats.add(index, &dial.step(&instruction));
+    }
+    stats
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Part {
+    One,
+    Two,
+}
+
+impl From<&str> for Part {
+    fn from(part: &str) -> Self {
+        match part {
+            "1" => Part::One,
+            "2" => Part::Two,
+            _ => panic!("Invalid part: {part}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Repl {
+    dial: Dial,
+    count: Count,
+    // State before each instruction or reset, for undo
+    history: Vec<(Dial, Count)>,
+}
+
+impl Repl {
+    fn new(dial: Dial) -> Self {
+        Self {
+            dial,
+            count: Count::default(),
+            history: Vec::new(),
+        }
+    }
+
+    fn write_state(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "position = {}, landed = {}, passed = {}",
+            self.dial.position, self.count.landed, self.count.passed
         )
-    );
+    }
+
+    // Handles one line of input: "undo", "reset <pos>" or instructions, errors are written
+    // to out and don't end the session
+    fn handle(&mut self, ind: usize, line: &str, out: &mut impl Write) -> std::io::Result<()> {
+        let line = line.trim();
+        if line == "undo" {
+            match self.history.pop() {
+                Some((dial, count)) => (self.dial, self.count) = (dial, count),
+                None => return writeln!(out, "error: nothing to undo"),
+            }
+            return self.write_state(out);
+        }
+        if let Some(position) = line.strip_prefix("reset") {
+            let size = self.dial.size;
+            match position.trim().parse::<Value>() {
+                Ok(position) if (0..size).contains(&position) => {
+                    self.history
+                        .push((self.dial, std::mem::take(&mut self.count)));
+                    self.dial = Dial::new(size, position);
+                }
+                _ => {
+                    return writeln!(
+                        out,
+                        "error: reset expects a position in [0, {size}), got {:?}",
+                        position.trim()
+                    );
+                }
+            }
+            return self.write_state(out);
+        }
+        match parse_line(line) {
+            Ok(instructions) => {
+                for (instruction, repeat) in instructions {
+                    for _ in 0..repeat {
+                        self.history.push((self.dial, self.count));
+                        self.count.add(&mut self.dial, &instruction);
+                        self.write_state(out)?;
+                    }
+                }
+                Ok(())
+            }
+            Err(error) => writeln!(out, "error: {}", ParseError { line: ind, ..error }),
+        }
+    }
+}
+
+// Reads commands until the end of input, state is written after each instruction
+fn interactive(dial: Dial, input: impl BufRead, out: &mut impl Write) -> std::io::Result<()> {
+    let mut repl = Repl::new(dial);
+    repl.write_state(out)?;
+    for (ind, line) in input.lines().enumerate() {
+        repl.handle(ind + 1, &line?, out)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+struct Args {
+    filename: Option<String>,
+    part: Part,
+    size: Value,
+    start: Value,
+    trace: Option<Format>,
+    stats: Option<Format>,
+    markers: Option<Vec<Value>>,
+    // Observed count and optional end position to find start positions for
+    invert: Option<Total>,
+    end: Option<Value>,
+    lenient: bool,
+    interactive: bool,
+}
+
+impl Args {
+    // Usage: day1 <filename>|--interactive [--part 1|2] [--size N] [--start N]
+    //             [--markers N,...] [--trace csv|json] [--stats csv|json]
+    //             [--invert COUNT [--end N]] [--lenient]
+    fn parse(mut args: impl Iterator<Item = String>) -> Self {
+        let mut filename = None;
+        let mut part = Part::Two;
+        let mut size = SIZE;
+        let mut start = START;
+        let mut trace = None;
+        let mut stats = None;
+        let mut markers = None;
+        let mut invert = None;
+        let mut end = None;
+        let mut lenient = false;
+        let mut interactive = false;
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .unwrap_or_else(|| panic!("{arg} expects a value"))
+            };
+            match arg.as_str() {
+                "--part" => part = Part::from(value().as_str()),
+                "--size" => size = value().parse().expect("size is a valid i64"),
+                "--start" => start = value().parse().expect("start is a valid i64"),
+                "--trace" => trace = Some(Format::from(value().as_str())),
+                "--stats" => stats = Some(Format::from(value().as_str())),
+                "--markers" => {
+                    markers = Some(
+                        parse_markers(&value(), 1)
+                            .unwrap_or_else(|e| panic!("Invalid markers: {e}")),
+                    )
+                }
+                "--invert" => invert = Some(value().parse().expect("count is a valid i128")),
+                "--end" => end = Some(value().parse().expect("end is a valid i64")),
+                "--lenient" => lenient = true,
+                "--interactive" => interactive = true,
+                _ if filename.is_none() => filename = Some(arg),
+                _ => panic!("Unexpected argument: {arg}"),
+            }
+        }
+        Self {
+            filename,
+            part,
+            size,
+            start,
+            trace,
+            stats,
+            markers,
+            invert,
+            end,
+            lenient,
+            interactive,
+        }
+    }
+}
+
+fn main() {
+    let args = Args::parse(std::env::args().skip(1));
+    let dial = Dial::new(args.size, args.start);
+    if args.interactive {
+        interactive(dial, std::io::stdin().lock(), &mut std::io::stdout().lock())
+            .expect("stdin and stdout are usable");
+        return;
+    }
+    let data = std::fs::read_to_string(args.filename.expect("filename")).expect("file exists");
+    let exit = |error: ParseError| -> ! {
+        eprintln!("{error}");
+        std::process::exit(1)
+    };
+    let markers = args
+        .markers
+        .or_else(|| parse_header(&data).map(|markers| markers.unwrap_or_else(|e| exit(e))));
+    let instructions = parse(data.as_bytes(), args.lenient)
+        .map(|instruction| instruction.unwrap_or_else(|e| exit(e)));
+    if let Some(count) = args.invert {
+        let instructions = instructions.collect::<Vec<_>>();
+        let steps = dial.size as Total * std::cmp::max(instructions.len(), 1) as Total;
+        if steps > MAX_INVERT_STEPS {
+            eprintln!(
+                "size = {} with {} instructions is too large to invert, \
+                 size * instructions must be <= {MAX_INVERT_STEPS}",
+                dial.size,
+                instructions.len()
+            );
+            std::process::exit(1)
+        }
+        for start in invert(dial.size, &instructions, args.part, count, args.end) {
+            println!("{start}");
+        }
+        return;
+    }
+    if let Some(format) = args.trace {
+        trace(dial, instructions, format, &mut std::io::stdout().lock())
+            .expect("stdout is writable");
+        return;
+    }
+    if let Some(format) = args.stats {
+        if dial.size > MAX_STATS_SIZE {
+            eprintln!(
+                "size = {} is too large for stats, must be <= {MAX_STATS_SIZE}",
+                dial.size
+            );
+            std::process::exit(1)
+        }
+        stats(dial, instructions)
+            .write(format, &mut std::io::stdout().lock())
+            .expect("stdout is writable");
+        return;
+    }
+    if let Some(markers) = markers {
+        if let Some(marker) = markers.iter().find(|m| !(0..dial.size).contains(m)) {
+            eprintln!("marker = {marker} must be in [0, {})", dial.size);
+            std::process::exit(1)
+        }
+        println!("marker,landed,passed");
+        for (marker, count) in markers
+            .iter()
+            .zip(solve_markers(dial, &markers, instructions))
+        {
+            println!("{marker},{},{}", count.landed, count.passed);
+        }
+        return;
+    }
+    println!("{}", solve(dial, instructions).get(args.part));
 }
 
 #[cfg(test)]
 mod tests {
     use crate::*;
 
+    // Reference implementation turning the dial one click at a time
+    fn simulate(mut dial: Dial, instructions: &[Instruction]) -> Count {
+        let mut count = Count::default();
+        for instruction in instructions {
+            let click = match instruction.direction {
+                Direction::Left => dial.size - 1,
+                Direction::Right => 1,
+            };
+            for _ in 0..instruction.value {
+                dial.position = (dial.position + click) % dial.size;
+                if dial.position == 0 {
+                    count.passed += 1;
+                }
+            }
+            if dial.position == 0 {
+                count.landed += 1;
+            }
+        }
+        count
+    }
+
+    // xorshift64*, good enough to generate test cases
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn range(&mut self, start: Value, end: Value) -> Value {
+            start + (self.next() % (end - start) as u64) as Value
+        }
+    }
+
+    fn matches(dial: Dial, instructions: &[Instruction]) -> bool {
+        solve(dial, instructions.iter().cloned()) == simulate(dial, instructions)
+    }
+
+    // Greedily drops instructions, lowers values and the start position while the case
+    // still fails
+    fn shrink(
+        mut dial: Dial,
+        mut instructions: Vec<Instruction>,
+        fails: impl Fn(Dial, &[Instruction]) -> bool,
+    ) -> (Dial, Vec<Instruction>) {
+        let mut shrunk = true;
+        while shrunk {
+            shrunk = false;
+            for ind in 0..instructions.len() {
+                let mut candidate = instructions.to_vec();
+                candidate.remove(ind);
+                if fails(dial, &candidate) {
+                    instructions = candidate;
+                    shrunk = true;
+                    break;
+                }
+            }
+            for ind in 0..instructions.len() {
+                let value = instructions[ind].value;
+                for smaller in [1, value / 2, value - 1] {
+                    if smaller <= 0 || smaller >= value {
+                        continue;
+                    }
+                    let mut candidate = instructions.to_vec();
+                    candidate[ind].value = smaller;
+                    if fails(dial, &candidate) {
+                        instructions = candidate;
+                        shrunk = true;
+                        break;
+                    }
+                }
+            }
+            for position in 0..dial.position {
+                let candidate = Dial::new(dial.size, position);
+                if fails(candidate, &instructions) {
+                    dial = candidate;
+                    shrunk = true;
+                    break;
+                }
+            }
+        }
+        (dial, instructions)
+    }
+
+    fn check(dial: Dial, instructions: Vec<Instruction>) {
+        if !matches(dial, &instructions) {
+            let (dial, instructions) = shrink(dial, instructions, |dial, instructions| {
+                !matches(dial, instructions)
+            });
+            panic!(
+                "{dial:?} {instructions:?}: solve = {:?}, simulate = {:?}",
+                solve(dial, instructions.iter().cloned()),
+                simulate(dial, &instructions)
+            );
+        }
+    }
+
     #[test]
-    fn test_turn_right() {
-        assert_eq!(
-            solve(95, vec![Instruction::new(Direction::Right, 60)].into_iter()),
-            1
-        );
-        assert_eq!(
-            solve(0, vec![Instruction::new(Direction::Right, 100)].into_iter()),
-            1
-        );
-        assert_eq!(
-            solve(50, vec![Instruction::new(Direction::Right, 50)].into_iter()),
-            1
-        );
-        assert_eq!(
-            solve(
-                50,
-                vec![Instruction::new(Direction::Right, 100)].into_iter()
-            ),
-            1
-        );
-        assert_eq!(
-            solve(
-                0,
-                vec![Instruction::new(Direction::Right, 1000)].into_iter()
-            ),
-            10
-        );
-        assert_eq!(
-            solve(
-                50,
-                vec![Instruction::new(Direction::Right, 1000)].into_iter()
-            ),
-            10
-        );
-        assert_eq!(
-            solve(50, vec![Instruction::new(Direction::Right, 5)].into_iter()),
-            0
-        );
-        assert_eq!(
-            solve(0, vec![Instruction::new(Direction::Right, 5)].into_iter()),
-            0
-        );
-        assert_eq!(
-            solve(50, vec![Instruction::new(Direction::Right, 1)].into_iter()),
-            0
-        );
-        assert_eq!(
-            solve(0, vec![Instruction::new(Direction::Right, 1)].into_iter()),
-            0
-        );
+    fn test_edge_cases() {
+        for direction in [Direction::Left, Direction::Right] {
+            for start in [0, 1, 5, 50, 95, 99] {
+                for value in [1, 5, 50, 51, 60, 99, 100, 101, 150, 1000] {
+                    check(
+                        Dial::new(SIZE, start),
+                        vec![Instruction::new(direction, value)],
+                    );
+                }
+            }
+        }
+    }
+
+    // Dial smaller than max_size and a few instructions, some turning more than once
+    fn random_case(rng: &mut Rng, max_size: Value) -> (Dial, Vec<Instruction>) {
+        let size = rng.range(1, max_size);
+        let dial = Dial::new(size, rng.range(0, size));
+        let instructions = (0..rng.range(1, 10))
+            .map(|_| {
+                let direction = if rng.next().is_multiple_of(2) {
+                    Direction::Left
+                } else {
+                    Direction::Right
+                };
+                Instruction::new(direction, rng.range(1, 3 * size + 2))
+            })
+            .collect();
+        (dial, instructions)
     }
 
     #[test]
-    fn test_turn_left() {
-        assert_eq!(
-            solve(50, vec![Instruction::new(Direction::Left, 51)].into_iter()),
-            1
-        );
-        assert_eq!(
-            solve(0, vec![Instruction::new(Direction::Left, 1)].into_iter()),
-            0
-        );
-        assert_eq!(
-            solve(0, vec![Instruction::new(Direction::Left, 5)].into_iter()),
-            0
-        );
-        assert_eq!(
-            solve(50, vec![Instruction::new(Direction::Left, 5)].into_iter()),
-            0
-        );
-        assert_eq!(
-            solve(50, vec![Instruction::new(Direction::Left, 1)].into_iter()),
-            0
-        );
-        assert_eq!(
-            solve(0, vec![Instruction::new(Direction::Left, 100)].into_iter()),
-            1
-        );
-        assert_eq!(
-            solve(50, vec![Instruction::new(Direction::Left, 50)].into_iter()),
-            1
-        );
-        assert_eq!(
-            solve(0, vec![Instruction::new(Direction::Left, 1000)].into_iter()),
-            10
-        );
-        assert_eq!(
-            solve(50, vec![Instruction::new(Direction::Left, 100)].into_iter()),
-            1
-        );
-        assert_eq!(
-            solve(
-                50,
-                vec![Instruction::new(Direction::Left, 1000)].into_iter()
-            ),
-            10
+    fn test_simulate() {
+        let mut rng = Rng(0x5eed);
+        for _ in 0..2000 {
+            let (dial, instructions) = random_case(&mut rng, 20);
+            check(dial, instructions);
+        }
+    }
+
+    #[test]
+    fn test_shrink() {
+        let (dial, instructions) = shrink(
+            Dial::new(SIZE, 42),
+            vec![
+                Instruction::new(Direction::Right, 3),
+                Instruction::new(Direction::Left, 70),
+                Instruction::new(Direction::Right, 12),
+            ],
+            |dial, instructions| dial.position >= 10 && instructions.iter().any(|i| i.value >= 7),
         );
+        assert_eq!(dial, Dial::new(SIZE, 10));
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].value, 7);
     }
 
     #[test]
     fn test_combine_one() {
         assert_eq!(
             solve(
-                50,
+                Dial::new(SIZE, 50),
                 vec![
                     Instruction::new(Direction::Left, 50),
                     Instruction::new(Direction::Right, 50)
                 ]
                 .into_iter()
-            ),
+            )
+            .passed,
             1
         );
 
         assert_eq!(
             solve(
-                50,
+                Dial::new(SIZE, 50),
                 vec![
                     Instruction::new(Direction::Left, 50),
                     Instruction::new(Direction::Left, 50)
                 ]
                 .into_iter()
-            ),
+            )
+            .passed,
             1
         );
 
         assert_eq!(
             solve(
-                50,
+                Dial::new(SIZE, 50),
                 vec![
                     Instruction::new(Direction::Right, 50),
                     Instruction::new(Direction::Left, 50)
                 ]
                 .into_iter()
-            ),
+            )
+            .passed,
             1
         );
 
         assert_eq!(
             solve(
-                50,
+                Dial::new(SIZE, 50),
                 vec![
                     Instruction::new(Direction::Right, 50),
                     Instruction::new(Direction::Right, 50)
                 ]
                 .into_iter()
-            ),
+            )
+            .passed,
             1
         );
     }
@@ -246,50 +1005,501 @@ mod tests {
     fn test_combine_two() {
         assert_eq!(
             solve(
-                50,
+                Dial::new(SIZE, 50),
                 vec![
                     Instruction::new(Direction::Left, 150),
                     Instruction::new(Direction::Left, 50)
                 ]
                 .into_iter()
-            ),
+            )
+            .passed,
             2
         );
 
         assert_eq!(
             solve(
-                50,
+                Dial::new(SIZE, 50),
                 vec![
                     Instruction::new(Direction::Left, 150),
                     Instruction::new(Direction::Left, 50)
                 ]
                 .into_iter()
-            ),
+            )
+            .passed,
             2
         );
 
         assert_eq!(
             solve(
-                50,
+                Dial::new(SIZE, 50),
                 vec![
                     Instruction::new(Direction::Right, 150),
                     Instruction::new(Direction::Left, 50)
                 ]
                 .into_iter()
-            ),
+            )
+            .passed,
             2
         );
 
         assert_eq!(
             solve(
-                50,
+                Dial::new(SIZE, 50),
                 vec![
                     Instruction::new(Direction::Right, 150),
                     Instruction::new(Direction::Right, 50)
                 ]
                 .into_iter()
-            ),
+            )
+            .passed,
             2
         );
     }
+
+    #[test]
+    fn test_dial_size() {
+        // Every click goes through 0 on a single position dial
+        assert_eq!(
+            solve(
+                Dial::new(1, 0),
+                vec![
+                    Instruction::new(Direction::Left, 7),
+                    Instruction::new(Direction::Right, 3)
+                ]
+                .into_iter()
+            ),
+            Count {
+                landed: 2,
+                passed: 10
+            }
+        );
+
+        let mut dial = Dial::new(7, 3);
+        assert_eq!(dial.rotate(&Instruction::new(Direction::Left, 3)), 1);
+        assert_eq!(dial.position, 0);
+        assert_eq!(dial.rotate(&Instruction::new(Direction::Left, 8)), 1);
+        assert_eq!(dial.position, 6);
+        assert_eq!(dial.rotate(&Instruction::new(Direction::Right, 15)), 3);
+        assert_eq!(dial.position, 0);
+    }
+
+    #[test]
+    fn test_large_values() {
+        // Overflows i16 and f64 precision
+        let mut dial = Dial::new(SIZE, START);
+        assert_eq!(
+            dial.rotate(&Instruction::new(Direction::Right, Value::MAX)),
+            (START as Total + Value::MAX as Total) / SIZE as Total
+        );
+        assert_eq!(
+            dial.position,
+            ((START as Total + Value::MAX as Total) % SIZE as Total) as Value
+        );
+
+        let mut dial = Dial::new(Value::MAX, Value::MAX - 1);
+        assert_eq!(
+            dial.rotate(&Instruction::new(Direction::Right, Value::MAX)),
+            1
+        );
+        assert_eq!(dial.position, Value::MAX - 1);
+        assert_eq!(
+            dial.rotate(&Instruction::new(Direction::Left, Value::MAX - 1)),
+            1
+        );
+        assert_eq!(dial.position, 0);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut dial = Dial::new(SIZE, START);
+        assert_eq!(
+            dial.step(&Instruction::new(Direction::Left, 168)),
+            Step {
+                before: 50,
+                raw: -118,
+                after: 82,
+                passed: 2
+            }
+        );
+        assert_eq!(
+            dial.step(&Instruction::new(Direction::Right, 18)),
+            Step {
+                before: 82,
+                raw: 100,
+                after: 0,
+                passed: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        let instructions = || {
+            vec![
+                Instruction::new(Direction::Left, 50),
+                Instruction::new(Direction::Right, 5),
+            ]
+            .into_iter()
+        };
+
+        let mut out = Vec::new();
+        trace(
+            Dial::new(SIZE, START),
+            instructions(),
+            Format::Csv,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "index,direction,value,before,raw,after,passed\n\
+             0,L,50,50,0,0,1\n\
+             1,R,5,0,5,5,0\n"
+        );
+
+        let mut out = Vec::new();
+        trace(
+            Dial::new(SIZE, START),
+            instructions(),
+            Format::Json,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                "{\"index\":0,\"direction\":\"L\",\"value\":50,",
+                "\"before\":50,\"raw\":0,\"after\":0,\"passed\":1}\n",
+                "{\"index\":1,\"direction\":\"R\",\"value\":5,",
+                "\"before\":0,\"raw\":5,\"after\":5,\"passed\":0}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |column, text: &str, kind| {
+            Err(ParseError {
+                line: 1,
+                column,
+                text: text.to_string(),
+                kind,
+            })
+        };
+        assert_eq!(
+            "".parse::<Instruction>(),
+            error(1, "", ParseErrorKind::Empty)
+        );
+        assert_eq!(
+            "X5".parse::<Instruction>(),
+            error(1, "X", ParseErrorKind::Direction)
+        );
+        assert_eq!(
+            "  é5".parse::<Instruction>(),
+            error(3, "é", ParseErrorKind::Direction)
+        );
+        assert_eq!(
+            "L".parse::<Instruction>(),
+            error(
+                2,
+                "",
+                ParseErrorKind::Value("".parse::<Value>().unwrap_err())
+            )
+        );
+        assert_eq!(
+            "R1é".parse::<Instruction>(),
+            error(
+                2,
+                "1é",
+                ParseErrorKind::Value("1é".parse::<Value>().unwrap_err())
+            )
+        );
+        assert_eq!(
+            "L0".parse::<Instruction>(),
+            error(2, "0", ParseErrorKind::NotPositive)
+        );
+        assert_eq!(
+            "R-3".parse::<Instruction>(),
+            error(2, "-3", ParseErrorKind::NotPositive)
+        );
+        assert_eq!(" R12 ".parse::<Instruction>().unwrap().value, 12);
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = "L5\n\n# comment\n  R7\nL0\n";
+        let errors = parse(data.as_bytes(), false)
+            .filter_map(Result::err)
+            .map(|error| (error.line, error.column))
+            .collect::<Vec<_>>();
+        assert_eq!(errors, vec![(2, 1), (5, 2)]);
+
+        let parsed = parse(data.as_bytes(), true).collect::<Vec<_>>();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[1].as_ref().unwrap().value, 7);
+        assert_eq!(
+            parsed[2].as_ref().unwrap_err().to_string(),
+            "line 5, column 2: value must be > 0: \"0\""
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut rng = Rng(0x57a7);
+        for _ in 0..500 {
+            let (mut dial, instructions) = random_case(&mut rng, 12);
+            let size = dial.size;
+
+            let out = stats(dial, instructions.iter().cloned());
+            let mut passed = vec![0; size as usize];
+            let mut landed = vec![0; size as usize];
+            for instruction in &instructions {
+                let click = match instruction.direction {
+                    Direction::Left => size - 1,
+                    Direction::Right => 1,
+                };
+                for _ in 0..instruction.value {
+                    dial.position = (dial.position + click) % size;
+                    passed[dial.position as usize] += 1;
+                }
+                landed[dial.position as usize] += 1;
+            }
+            assert_eq!(out.passed(), passed, "{instructions:?}");
+            assert_eq!(out.landed, landed, "{instructions:?}");
+        }
+    }
+
+    #[test]
+    fn test_stats_zero_events() {
+        let out = stats(
+            Dial::new(SIZE, START),
+            vec![
+                Instruction::new(Direction::Left, 10),
+                Instruction::new(Direction::Right, 260),
+                Instruction::new(Direction::Right, 5),
+                Instruction::new(Direction::Left, 2),
+                Instruction::new(Direction::Left, 2),
+                Instruction::new(Direction::Left, 1),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(out.zero_events, vec![(1, 3), (5, 1)]);
+        assert_eq!(out.longest_streak, 3);
+        assert_eq!(out.landed[0], 2);
+        assert_eq!(out.passed()[0], 4);
+
+        let out = stats(
+            Dial::new(2, 0),
+            vec![Instruction::new(Direction::Right, 3)].into_iter(),
+        );
+        let mut csv = Vec::new();
+        out.write(Format::Csv, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "kind,index,passed,landed,length\n\
+             position,0,1,0,\n\
+             position,1,2,1,\n\
+             zero_event,0,1,,\n\
+             longest_streak,,,,0\n"
+        );
+        let mut json = Vec::new();
+        out.write(Format::Json, &mut json).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "{\"positions\":[{\"position\":0,\"passed\":1,\"landed\":0},\
+             {\"position\":1,\"passed\":2,\"landed\":1}],\
+             \"longest_streak\":0,\"zero_events\":[{\"index\":0,\"passed\":1}]}\n"
+        );
+    }
+
+    #[test]
+    fn test_markers() {
+        let mut rng = Rng(0x3a7c);
+        for _ in 0..500 {
+            let (dial, instructions) = random_case(&mut rng, 12);
+            let size = dial.size;
+            let markers = (0..size).collect::<Vec<_>>();
+            let counts = solve_markers(dial, &markers, instructions.iter().cloned());
+            assert_eq!(counts[0], solve(dial, instructions.iter().cloned()));
+
+            let out = stats(dial, instructions.iter().cloned());
+            let passed = out.passed();
+            for marker in markers {
+                let count = &counts[marker as usize];
+                assert_eq!(count.passed, passed[marker as usize], "{instructions:?}");
+                assert_eq!(
+                    count.landed, out.landed[marker as usize],
+                    "{instructions:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_markers() {
+        assert_eq!(parse_markers("0, 25,50  75", 1), Ok(vec![0, 25, 50, 75]));
+        assert_eq!(parse_markers("", 1), Ok(vec![]));
+        assert_eq!(
+            parse_markers("1,é", 9),
+            Err(ParseError {
+                line: 1,
+                column: 11,
+                text: "é".to_string(),
+                kind: ParseErrorKind::Value("é".parse::<Value>().unwrap_err()),
+            })
+        );
+        assert_eq!(parse_header("markers: 1 2\nL5"), Some(Ok(vec![1, 2])));
+        assert_eq!(
+            parse_header("markers: 1 x\nL5")
+                .unwrap()
+                .unwrap_err()
+                .column,
+            12
+        );
+        assert_eq!(parse_header("L5\nmarkers: 1"), None);
+
+        let parsed = parse("markers: 1\nR5\n".as_bytes(), false).collect::<Vec<_>>();
+        assert_eq!(parsed, vec![Ok(Instruction::new(Direction::Right, 5))]);
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let l = |value| Instruction::new(Direction::Left, value);
+        let r = |value| Instruction::new(Direction::Right, value);
+        assert_eq!(
+            parse_line("R50x3, -5 +7,L2 # R1"),
+            Ok(vec![(r(50), 3), (l(5), 1), (r(7), 1), (l(2), 1)])
+        );
+        assert_eq!(parse_line("  # comment"), Ok(vec![]));
+        assert_eq!(parse_line(",,"), Err(error(1, "", ParseErrorKind::Empty)));
+        assert_eq!(
+            parse_line("L1, R5x0"),
+            Err(error(
+                8,
+                "0",
+                ParseErrorKind::Repeat("0".parse::<NonZeroUsize>().unwrap_err())
+            ))
+        );
+        assert_eq!(
+            parse_line("L1 é2"),
+            Err(error(4, "é", ParseErrorKind::Direction))
+        );
+        assert_eq!(
+            parse_line("L1 -x2"),
+            Err(error(
+                5,
+                "",
+                ParseErrorKind::Value("".parse::<Value>().unwrap_err())
+            ))
+        );
+
+        let parsed = parse("R50x3 # three\n-5\n".as_bytes(), false)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        assert_eq!(parsed, vec![r(50), r(50), r(50), l(5)]);
+        assert_eq!(
+            solve(Dial::new(SIZE, START), parsed.into_iter()),
+            Count {
+                landed: 2,
+                passed: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_interactive() {
+        let input = "R50\nundo\nundo\nL50x2, R100\nreset 0\nL100\nreset 100\nX1\nundo\n";
+        let mut out = Vec::new();
+        interactive(Dial::new(SIZE, START), input.as_bytes(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "position = 50, landed = 0, passed = 0\n\
+             position = 0, landed = 1, passed = 1\n\
+             position = 50, landed = 0, passed = 0\n\
+             error: nothing to undo\n\
+             position = 0, landed = 1, passed = 1\n\
+             position = 50, landed = 1, passed = 1\n\
+             position = 50, landed = 1, passed = 2\n\
+             position = 0, landed = 0, passed = 0\n\
+             position = 0, landed = 1, passed = 1\n\
+             error: reset expects a position in [0, 100), got \"100\"\n\
+             error: line 8, column 1: direction must be 'L', 'R', '-' or '+': \"X\"\n\
+             position = 0, landed = 0, passed = 0\n"
+        );
+    }
+
+    #[test]
+    fn test_invert() {
+        use std::iter::once;
+
+        let instructions = vec![
+            Instruction::new(Direction::Right, 30),
+            Instruction::new(Direction::Left, 5),
+        ];
+        // R30 goes through 0 from [70, 100), L5 then goes through 0 from [71, 75]
+        let starts = invert(SIZE, &instructions, Part::Two, 1, None).collect::<Vec<_>>();
+        assert_eq!(starts, once(70).chain(76..100).collect::<Vec<_>>());
+        assert_eq!(
+            invert(SIZE, &instructions, Part::Two, 0, None).collect::<Vec<_>>(),
+            (0..70).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            invert(SIZE, &instructions, Part::Two, 2, None).collect::<Vec<_>>(),
+            (71..=75).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            invert(SIZE, &instructions, Part::Two, 2, Some(0)).collect::<Vec<_>>(),
+            vec![75]
+        );
+        assert_eq!(
+            invert(SIZE, &instructions, Part::One, 1, None).collect::<Vec<_>>(),
+            vec![70, 75]
+        );
+        assert_eq!(
+            invert(SIZE, &instructions, Part::Two, 3, None).collect::<Vec<_>>(),
+            vec![]
+        );
+
+        for start in starts {
+            assert_eq!(
+                solve(Dial::new(SIZE, start), instructions.iter().cloned()).passed,
+                1
+            );
+        }
+
+        // Starts are yielded without scanning the whole dial
+        assert_eq!(
+            invert(Value::MAX, &instructions, Part::Two, 0, None).next(),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_landed() {
+        let count = solve(
+            Dial::new(SIZE, 50),
+            vec![
+                Instruction::new(Direction::Left, 68),
+                Instruction::new(Direction::Left, 30),
+                Instruction::new(Direction::Right, 48),
+                Instruction::new(Direction::Left, 5),
+                Instruction::new(Direction::Right, 60),
+                Instruction::new(Direction::Left, 55),
+                Instruction::new(Direction::Left, 1),
+                Instruction::new(Direction::Left, 99),
+                Instruction::new(Direction::Right, 14),
+                Instruction::new(Direction::Left, 82),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(
+            count,
+            Count {
+                landed: 3,
+                passed: 6
+            }
+        );
+        assert_eq!(count.get(Part::One), 3);
+        assert_eq!(count.get(Part::Two), 6);
+    }
 }
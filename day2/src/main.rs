@@ -1,45 +1,164 @@
-use std::collections::HashSet;
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::num::ParseIntError;
+use std::ops::{Add, Div, Mul, Rem, Sub};
+
+// Unsigned integer IDs, arithmetic that can overflow is checked
+trait Id:
+    Copy
+    + Ord
+    + fmt::Debug
+    + fmt::Display
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + Send
+    + Sync
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const BITS: u32;
+
+    fn from_u32(value: u32) -> Self;
+    // Truncates values that don't fit
+    fn as_u32(self) -> u32;
+    fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn overflowing_add(self, other: Self) -> (Self, bool);
+    fn overflowing_sub(self, other: Self) -> (Self, bool);
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn checked_pow(self, exp: u32) -> Option<Self>;
+}
+
+macro_rules! impl_id {
+    ($($t:ty),*) => {
+        $(impl Id for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            const BITS: u32 = <$t>::BITS;
+
+            fn from_u32(value: u32) -> Self {
+                value as $t
+            }
+
+            fn as_u32(self) -> u32 {
+                self as u32
+            }
+
+            fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError> {
+                <$t>::from_str_radix(src, radix)
+            }
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+
+            fn overflowing_add(self, other: Self) -> (Self, bool) {
+                <$t>::overflowing_add(self, other)
+            }
+
+            fn overflowing_sub(self, other: Self) -> (Self, bool) {
+                <$t>::overflowing_sub(self, other)
+            }
+
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                <$t>::checked_mul(self, other)
+            }
+
+            fn checked_pow(self, exp: u32) -> Option<Self> {
+                <$t>::checked_pow(self, exp)
+            }
+        })*
+    };
+}
+
+impl_id!(u64, u128);
+
+#[derive(Debug, PartialEq)]
+struct Overflow {
+    bits: u32,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "sum of invalid IDs doesn't fit in {} bits", self.bits)
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+fn format_radix<T: Id>(mut value: T, radix: u32) -> String {
+    let mut digits = Vec::new();
+    loop {
+        let digit = (value % T::from_u32(radix)).as_u32();
+        digits.push(char::from_digit(digit, radix).expect("digit < radix"));
+        value = value / T::from_u32(radix);
+        if value == T::ZERO {
+            break;
+        }
+    }
+    digits.into_iter().rev().collect()
+}
+
+fn checked_sum<T: Id>(values: impl IntoIterator<Item = T>) -> Result<T, Overflow> {
+    values
+        .into_iter()
+        .try_fold(T::ZERO, |sum, value| sum.checked_add(value))
+        .ok_or(Overflow { bits: T::BITS })
+}
 
 #[derive(Debug)]
-struct Range {
-    start: u64,
-    end: u64,
+struct Range<T> {
+    start: T,
+    end: T,
+    // Base the IDs are written in, in [2, 36]
+    radix: u32,
 }
 
 #[derive(Debug)]
-struct Group {
+struct Group<T> {
     // TODO: pick better names
-    value: u64,
-    base: u64,
+    value: T,
+    base: T,
     n: u32,
-    next_base: u64,
+    next_base: T,
     size: u32,
     group_size: u32,
+    radix: T,
+    // Set once value doesn't fit in T
+    exhausted: bool,
 }
 
-impl Group {
-    fn from(value: u64, size: u32) -> Self {
-        let n_digits = Group::get_n_digits(value);
+impl<T: Id> Group<T> {
+    fn from(value: T, size: u32, radix: u32) -> Self {
+        let n_digits = Group::get_n_digits(value, radix);
+        let radix = T::from_u32(radix);
         let mut out = if n_digits.is_multiple_of(size) {
             let n = n_digits / size;
             Self {
-                value: 0,
+                value: T::ZERO,
                 n,
-                base: value / 10u64.pow(n_digits - n),
-                next_base: 10u64.pow(n),
+                base: value / radix.checked_pow(n_digits - n).expect("< value"),
+                next_base: radix.checked_pow(n).expect("< value"),
                 size: n_digits,
                 group_size: size,
+                radix,
+                exhausted: false,
             }
         } else {
             let n = n_digits.div_ceil(size);
-            let next_base = (10u64).pow(n);
+            let next_base = radix.checked_pow(n).expect("< value");
             Self {
-                value: 0,
-                base: next_base / 10,
+                value: T::ZERO,
+                base: next_base / radix,
                 next_base,
                 n,
                 size: n * size,
                 group_size: size,
+                radix,
+                exhausted: false,
             }
         };
         out.compute_value();
@@ -47,92 +166,714 @@ impl Group {
     }
 
     fn next(&mut self) {
-        self.base += 1;
+        self.base = self.base + T::ONE;
         if self.base == self.next_base {
             // Skip odd numbers
             self.n += 1;
-            self.next_base *= 10;
+            let Some(next_base) = self.next_base.checked_mul(self.radix) else {
+                self.exhausted = true;
+                return;
+            };
+            self.next_base = next_base;
             self.size = self.group_size * self.n;
         }
         self.compute_value();
     }
 
     fn compute_value(&mut self) {
-        self.value = self.base
-            * (0..self.size)
-                .step_by(self.n as usize)
-                .map(|p| 10_u64.pow(p))
-                .sum::<u64>()
+        match (0..self.size)
+            .step_by(self.n as usize)
+            .try_fold(T::ZERO, |sum, p| {
+                sum.checked_add(self.radix.checked_pow(p)?)
+            })
+            .and_then(|factor| self.base.checked_mul(factor))
+        {
+            Some(value) => self.value = value,
+            None => self.exhausted = true,
+        }
     }
 
-    fn get_n_digits(mut value: u64) -> u32 {
+    fn get_n_digits(mut value: T, radix: u32) -> u32 {
+        let radix = T::from_u32(radix);
         let mut out = 0;
-        while value > 0 {
-            value /= 10;
+        while value > T::ZERO {
+            value = value / radix;
             out += 1;
         }
         out
     }
 }
 
-impl Range {
-    fn sum_invalid_ids(&self) -> u64 {
-        let mut invalid_ids = HashSet::default();
-        for size in 2..=Group::get_n_digits(self.end) {
-            self.get_invalid_ids_for_group(size, &mut invalid_ids);
+// Number of times a block must be repeated for an ID to be invalid
+#[derive(Debug, Clone, PartialEq)]
+enum Rule {
+    // Part 1
+    Twice,
+    // Part 2
+    AtLeastTwice,
+    // Counts below 2 are ignored, every ID is its own block repeated once
+    Counts(Vec<u32>),
+}
+
+impl Rule {
+    // Group sizes to check for IDs up to n_digits long
+    fn sizes(&self, n_digits: u32) -> Vec<u32> {
+        let counts = match self {
+            Rule::Twice => vec![2],
+            Rule::AtLeastTwice => (2..=n_digits).collect(),
+            Rule::Counts(counts) => counts.clone(),
+        };
+        counts
+            .into_iter()
+            .filter(|count| (2..=n_digits).contains(count))
+            .collect()
+    }
+}
+
+impl From<&str> for Rule {
+    fn from(part: &str) -> Self {
+        match part {
+            "1" => Rule::Twice,
+            "2" => Rule::AtLeastTwice,
+            _ => panic!("Invalid part: {part}"),
         }
-        invalid_ids.into_iter().sum()
     }
+}
 
-    fn get_invalid_ids_for_group(&self, size: u32, invalid_ids: &mut HashSet<u64>) {
-        let mut group = Group::from(self.start, size);
-        while group.value < self.start {
-            group.next();
+// Digit pattern making an ID invalid, new rules only need is_match
+trait Pattern: fmt::Debug + Sync {
+    // digits is the ID written in its radix
+    fn is_match(&self, digits: &str) -> bool;
+
+    // Repeated block rules are walked with Group or summed in closed form instead of
+    // checking every ID of a range
+    fn as_rule(&self) -> Option<&Rule> {
+        None
+    }
+}
+
+impl Pattern for Rule {
+    fn is_match(&self, digits: &str) -> bool {
+        minimal_block(digits, self).is_some()
+    }
+
+    fn as_rule(&self) -> Option<&Rule> {
+        Some(self)
+    }
+}
+
+// Reads the same both ways, e.g. 12321
+#[derive(Debug)]
+struct Palindrome;
+
+impl Pattern for Palindrome {
+    fn is_match(&self, digits: &str) -> bool {
+        digits.bytes().eq(digits.bytes().rev())
+    }
+}
+
+// First half (rounded down) is repeated right after itself, e.g. 123123 or 1231234
+#[derive(Debug)]
+struct HalfPrefix;
+
+impl Pattern for HalfPrefix {
+    fn is_match(&self, digits: &str) -> bool {
+        let half = digits.len() / 2;
+        half > 0 && digits[half..].starts_with(&digits[..half])
+    }
+}
+
+// Starts with a block repeated at least min_repeats times, followed by any suffix, e.g.
+// 1212125 for 3
+#[derive(Debug)]
+struct BlockThenSuffix {
+    min_repeats: usize,
+}
+
+impl Pattern for BlockThenSuffix {
+    fn is_match(&self, digits: &str) -> bool {
+        (1..=digits.len() / self.min_repeats).any(|block_size| {
+            let mut blocks = digits.as_bytes()[..block_size * self.min_repeats].chunks(block_size);
+            let first = blocks.next();
+            blocks.all(|block| Some(block) == first)
+        })
+    }
+}
+
+// E.g. "palindrome", "half-prefix" or "block-suffix:3"
+fn parse_pattern(spec: &str) -> Box<dyn Pattern> {
+    match spec.split_once(':') {
+        None if spec == "palindrome" => Box::new(Palindrome),
+        None if spec == "half-prefix" => Box::new(HalfPrefix),
+        Some(("block-suffix", min_repeats)) => Box::new(BlockThenSuffix {
+            min_repeats: match min_repeats.parse() {
+                Ok(min_repeats) if min_repeats >= 2 => min_repeats,
+                _ => panic!("Invalid repeat count: {min_repeats}, must be >= 2"),
+            },
+        }),
+        _ => panic!("Invalid pattern: {spec}"),
+    }
+}
+
+fn mobius(mut n: u32) -> i32 {
+    let mut out = 1;
+    let mut p = 2;
+    while p * p <= n {
+        if n.is_multiple_of(p) {
+            n /= p;
+            if n.is_multiple_of(p) {
+                return 0;
+            }
+            out = -out;
         }
-        while group.value <= self.end {
-            invalid_ids.insert(group.value);
+        p += 1;
+    }
+    if n > 1 { -out } else { out }
+}
+
+// Sum of the n_digits long IDs in [start, end] made of a block of block_size digits repeated,
+// None on overflow
+fn sum_repeated<T: Id>(start: T, end: T, n_digits: u32, block_size: u32, radix: T) -> Option<T> {
+    // E.g. 123123 = 123 * 1001
+    let factor = (0..n_digits)
+        .step_by(block_size as usize)
+        .try_fold(T::ZERO, |sum, p| sum.checked_add(radix.checked_pow(p)?))?;
+    let mut first = start / factor;
+    if start % factor != T::ZERO {
+        first = first + T::ONE;
+    }
+    // block_size < n_digits so these don't overflow
+    let first = std::cmp::max(first, radix.checked_pow(block_size - 1)?);
+    let last = std::cmp::min(end / factor, radix.checked_pow(block_size)? - T::ONE);
+    if first > last {
+        return Some(T::ZERO);
+    }
+    let count = last - first + T::ONE;
+    let two = T::from_u32(2);
+    // Either count or first + last is even
+    let series = if count % two == T::ZERO {
+        (count / two).checked_mul(first.checked_add(last)?)?
+    } else {
+        count.checked_mul(first.checked_add(last)? / two)?
+    };
+    factor.checked_mul(series)
+}
+
+impl<T: Id> Range<T> {
+    // Closed form: IDs made of blocks of a given size are an arithmetic series. IDs made of
+    // several block sizes (e.g. 111111) are deduplicated with inclusion-exclusion.
+    fn sum_invalid_ids(&self, rule: &Rule) -> Result<T, Overflow> {
+        let radix = T::from_u32(self.radix);
+        // Net sum is carries * 2^BITS + low: an ID can be added several times before being
+        // removed, so only the final value has to fit
+        let mut low = T::ZERO;
+        let mut carries = 0i64;
+        for n_digits in std::cmp::max(Group::get_n_digits(self.start, self.radix), 1)
+            ..=Group::get_n_digits(self.end, self.radix)
+        {
+            let start = std::cmp::max(self.start, radix.checked_pow(n_digits - 1).expect("<= end"));
+            let end = radix
+                .checked_pow(n_digits)
+                .map_or(self.end, |max| std::cmp::min(self.end, max - T::ONE));
+            let block_sizes = rule
+                .sizes(n_digits)
+                .into_iter()
+                .filter(|size| n_digits.is_multiple_of(*size))
+                .map(|size| n_digits / size)
+                .collect::<Vec<_>>();
+            // Minimal block sizes to sum: divisors of an allowed block size
+            let minimal_sizes = (1..n_digits)
+                .filter(|d| block_sizes.iter().any(|b| b.is_multiple_of(*d)))
+                .collect::<Vec<_>>();
+            // IDs with minimal block size d = sum over e | d of mobius(d / e) * S(e)
+            for e in 1..n_digits {
+                let coefficient = minimal_sizes
+                    .iter()
+                    .filter(|d| d.is_multiple_of(e))
+                    .map(|d| mobius(d / e))
+                    .sum::<i32>();
+                if coefficient == 0 {
+                    continue;
+                }
+                // Every ID of the term is invalid, so it fits if the total does
+                let sum = sum_repeated(start, end, n_digits, e, radix)
+                    .ok_or(Overflow { bits: T::BITS })?;
+                for _ in 0..coefficient.unsigned_abs() {
+                    let overflow;
+                    if coefficient > 0 {
+                        (low, overflow) = low.overflowing_add(sum);
+                        carries += overflow as i64;
+                    } else {
+                        (low, overflow) = low.overflowing_sub(sum);
+                        carries -= overflow as i64;
+                    }
+                }
+            }
+        }
+        match carries {
+            0 => Ok(low),
+            _ => Err(Overflow { bits: T::BITS }),
+        }
+    }
+
+    // Reference implementation walking through every invalid ID
+    fn sum_invalid_ids_enumerated(&self, rule: &Rule) -> Result<T, Overflow> {
+        checked_sum(self.invalid_ids(rule))
+    }
+
+    // IDs matching pattern in ascending order
+    fn matching_ids<'a>(&'a self, pattern: &'a dyn Pattern) -> Box<dyn Iterator<Item = T> + 'a> {
+        if let Some(rule) = pattern.as_rule() {
+            return Box::new(self.invalid_ids(rule));
+        }
+        let end = self.end;
+        Box::new(
+            std::iter::successors(Some(self.start), move |&id| (id < end).then(|| id + T::ONE))
+                .filter(|&id| pattern.is_match(&format_radix(id, self.radix))),
+        )
+    }
+
+    fn sum_matching(&self, pattern: &dyn Pattern, enumerate: bool) -> Result<T, Overflow> {
+        match pattern.as_rule() {
+            Some(rule) if !enumerate => self.sum_invalid_ids(rule),
+            Some(rule) => self.sum_invalid_ids_enumerated(rule),
+            _ => checked_sum(self.matching_ids(pattern)),
+        }
+    }
+
+    fn invalid_ids(&self, rule: &Rule) -> InvalidIds<T> {
+        let groups = rule
+            .sizes(Group::get_n_digits(self.end, self.radix))
+            .into_iter()
+            .map(|size| self.get_group(size))
+            .collect();
+        InvalidIds {
+            groups,
+            end: self.end,
+        }
+    }
+
+    // First group of size >= start, 0 has no digits to repeat
+    fn get_group(&self, size: u32) -> Group<T> {
+        let mut group = Group::from(std::cmp::max(self.start, T::ONE), size, self.radix);
+        while !group.exhausted && group.value < self.start {
             group.next();
         }
+        group
     }
 }
 
-impl From<&str> for Range {
-    fn from(pair: &str) -> Self {
-        let (n1, n2) = pair.split_once("-").expect("pair is delimited by '-'");
-        Self {
-            start: n1.parse().expect("first pair item is a valid number"),
-            end: n2.parse().expect("second pair item is a valid number"),
+// Invalid IDs in ascending order, merged from one group per allowed size
+struct InvalidIds<T> {
+    groups: Vec<Group<T>>,
+    end: T,
+}
+
+impl<T: Id> Iterator for InvalidIds<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self
+            .groups
+            .iter()
+            .filter(|group| !group.exhausted)
+            .map(|group| group.value)
+            .min()
+            .filter(|value| *value <= self.end)?;
+        // Same ID can be made of several group sizes, e.g. 111111
+        for group in self.groups.iter_mut() {
+            if !group.exhausted && group.value == value {
+                group.next();
+            }
         }
+        Some(value)
     }
 }
 
-fn parse(data: &str) -> Vec<Range> {
-    data.split(',').map(Range::from).collect()
+impl<T: Id> fmt::Display for Range<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            format_radix(self.start, self.radix),
+            format_radix(self.end, self.radix)
+        )
+    }
 }
 
-fn main() {
-    println!(
-        "{}",
-        parse(
-            std::fs::read_to_string(std::env::args().nth(1).expect("filename"))
-                .expect("file exists")
-                .as_str()
-                .trim_end(),
+impl<T: Id> Range<T> {
+    // Errors are reported on line 1, e.g. "11-22" or "11 - 22"
+    fn parse(pair: &str, radix: u32) -> Result<Self, ParseError> {
+        let error = |text: &str, kind| ParseError {
+            line: 1,
+            column: pair[..text.as_ptr() as usize - pair.as_ptr() as usize]
+                .chars()
+                .count()
+                + 1,
+            text: text.to_string(),
+            kind,
+        };
+        let Some((n1, n2)) = pair.split_once('-') else {
+            return Err(error(pair, ParseErrorKind::Dash));
+        };
+        let number = |n: &str| {
+            let n = n.trim();
+            T::from_str_radix(n, radix).map_err(|e| error(n, ParseErrorKind::Number(e)))
+        };
+        let out = Self {
+            start: number(n1)?,
+            end: number(n2)?,
+            radix,
+        };
+        if out.start > out.end {
+            return Err(error(pair, ParseErrorKind::Reversed));
+        }
+        Ok(out)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ParseErrorKind {
+    Dash,
+    Number(ParseIntError),
+    Reversed,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::Dash => write!(f, "range must be delimited by '-'"),
+            ParseErrorKind::Number(e) => write!(f, "invalid number ({e})"),
+            ParseErrorKind::Reversed => write!(f, "range start is greater than its end"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct ParseError {
+    // 1-based, in characters for the column
+    line: usize,
+    column: usize,
+    text: String,
+    kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}: {:?}",
+            self.line, self.column, self.kind, self.text
         )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Ranges separated by ',' and/or newlines, whitespace around them is ignored
+fn parse<T: Id>(data: &str, radix: u32) -> Result<Vec<Range<T>>, ParseError> {
+    data.split([',', '\n'])
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            Range::parse(pair, radix).map_err(|error| {
+                let before = &data[..pair.as_ptr() as usize - data.as_ptr() as usize];
+                let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+                ParseError {
+                    line: before.matches('\n').count() + 1,
+                    column: before[line_start..].chars().count() + error.column,
+                    ..error
+                }
+            })
+        })
+        .collect()
+}
+
+// Sorted, with overlapping and adjacent ranges merged so that no ID is counted twice
+fn merge<T: Id>(mut ranges: Vec<Range<T>>) -> Vec<Range<T>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut out: Vec<Range<T>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match out.last_mut() {
+            Some(last)
+                if range.start <= last.end || last.end.checked_add(T::ONE) == Some(range.start) =>
+            {
+                last.end = std::cmp::max(last.end, range.end);
+            }
+            _ => out.push(range),
+        }
+    }
+    out
+}
+
+// Sum for each range in order, ranges are split in contiguous chunks over jobs threads
+fn sum_ranges<T: Id>(
+    ranges: &[Range<T>],
+    pattern: &dyn Pattern,
+    enumerate: bool,
+    jobs: usize,
+) -> Vec<Result<T, Overflow>> {
+    let sum = |range: &Range<T>| range.sum_matching(pattern, enumerate);
+    if jobs <= 1 {
+        return ranges.iter().map(sum).collect();
+    }
+    std::thread::scope(|scope| {
+        ranges
+            .chunks(ranges.len().div_ceil(jobs).max(1))
+            .map(|chunk| scope.spawn(move || chunk.iter().map(sum).collect::<Vec<_>>()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("thread doesn't panic"))
+            .collect()
+    })
+}
+
+fn solve<T: Id>(
+    ranges: &[Range<T>],
+    pattern: &dyn Pattern,
+    enumerate: bool,
+    jobs: usize,
+) -> Result<T, Overflow> {
+    checked_sum(
+        sum_ranges(ranges, pattern, enumerate, jobs)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?,
+    )
+}
+
+// Smallest block allowed by rule that id is made of, with its repeat count, None for valid
+// IDs. E.g. 824824824 gives ("824", 3).
+fn is_invalid<T: Id>(id: T, radix: u32, rule: &Rule) -> Option<(String, u32)> {
+    let digits = format_radix(id, radix);
+    minimal_block(&digits, rule)
+        .map(|(block_size, count)| (digits[..block_size].to_string(), count))
+}
+
+// Size and repeat count of the smallest block allowed by rule
+fn minimal_block(digits: &str, rule: &Rule) -> Option<(usize, u32)> {
+    let n_digits = digits.len() as u32;
+    rule.sizes(n_digits)
         .into_iter()
-        .map(|range| range.sum_invalid_ids())
-        .sum::<u64>()
-    );
+        .filter(|count| n_digits.is_multiple_of(*count))
+        .map(|count| (digits.len() / count as usize, count))
+        .filter(|(block_size, _)| {
+            let mut blocks = digits.as_bytes().chunks(*block_size);
+            let first = blocks.next();
+            blocks.all(|block| Some(block) == first)
+        })
+        .min()
+}
+
+// One ID per line, blank lines are skipped
+fn check<T: Id>(input: impl BufRead, args: &Args, out: &mut impl Write) -> std::io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        let id = line.trim();
+        if id.is_empty() {
+            continue;
+        }
+        match T::from_str_radix(id, args.radix) {
+            Ok(value) => match args.pattern.as_rule() {
+                Some(rule) => match is_invalid(value, args.radix, rule) {
+                    Some((block, count)) => writeln!(out, "{id} invalid {block} {count}")?,
+                    None => writeln!(out, "{id} valid")?,
+                },
+                None if args.pattern.is_match(&format_radix(value, args.radix)) => {
+                    writeln!(out, "{id} invalid")?
+                }
+                None => writeln!(out, "{id} valid")?,
+            },
+            Err(e) => writeln!(out, "{id} error {e}")?,
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Output {
+    // Every invalid ID, written in the range radix
+    List,
+    Count,
+    Sum,
+}
+
+#[derive(Debug)]
+struct Args {
+    filename: Option<String>,
+    pattern: Box<dyn Pattern>,
+    width: u32,
+    radix: u32,
+    output: Output,
+    // Adds a line per range, prefixed with the range
+    per_range: bool,
+    enumerate: bool,
+    // Threads to sum ranges on
+    jobs: usize,
+    // Checks IDs read from stdin instead
+    check: bool,
+}
+
+impl Args {
+    // Usage: day2 <filename>|--check [--part 1|2] [--repeats N,...] [--pattern NAME]
+    //             [--width 64|128]
+    //             [--radix N] [--list|--count|--sum] [--per-range] [--enumerate] [--jobs N]
+    fn parse(mut args: impl Iterator<Item = String>) -> Self {
+        let mut filename = None;
+        let mut pattern: Box<dyn Pattern> = Box::new(Rule::AtLeastTwice);
+        let mut width = 64;
+        let mut radix = 10;
+        let mut output = Output::Sum;
+        let mut per_range = false;
+        let mut enumerate = false;
+        let mut jobs = 1;
+        let mut check = false;
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .unwrap_or_else(|| panic!("{arg} expects a value"))
+            };
+            match arg.as_str() {
+                "--part" => pattern = Box::new(Rule::from(value().as_str())),
+                "--repeats" => {
+                    pattern = Box::new(Rule::Counts(
+                        value()
+                            .split(',')
+                            .map(|count| match count.parse() {
+                                Ok(count) if count >= 2 => count,
+                                _ => panic!("Invalid repeat count: {count}, must be >= 2"),
+                            })
+                            .collect(),
+                    ))
+                }
+                "--pattern" => pattern = parse_pattern(&value()),
+                "--width" => width = value().parse().expect("width is 64 or 128"),
+                "--radix" => {
+                    radix = match value().parse() {
+                        Ok(radix) if (2..=36).contains(&radix) => radix,
+                        _ => panic!("Invalid radix, must be in [2, 36]"),
+                    }
+                }
+                "--list" => output = Output::List,
+                "--count" => output = Output::Count,
+                "--sum" => output = Output::Sum,
+                "--per-range" => per_range = true,
+                "--enumerate" => enumerate = true,
+                "--jobs" => {
+                    jobs = match value().parse() {
+                        Ok(jobs) if jobs > 0 => jobs,
+                        _ => panic!("Invalid jobs, must be > 0"),
+                    }
+                }
+                "--check" => check = true,
+                _ if filename.is_none() => filename = Some(arg),
+                _ => panic!("Unexpected argument: {arg}"),
+            }
+        }
+        Self {
+            filename,
+            pattern,
+            width,
+            radix,
+            output,
+            per_range,
+            enumerate,
+            jobs,
+            check,
+        }
+    }
+}
+
+fn write_output<T: Id>(
+    ranges: &[Range<T>],
+    args: &Args,
+    out: &mut impl Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let prefix = |range: &Range<T>| {
+        if args.per_range {
+            format!("{range} ")
+        } else {
+            String::new()
+        }
+    };
+    match args.output {
+        Output::List => {
+            for range in ranges {
+                for id in range.matching_ids(args.pattern.as_ref()) {
+                    writeln!(out, "{}{}", prefix(range), format_radix(id, range.radix))?;
+                }
+            }
+        }
+        Output::Count => {
+            let mut total = 0;
+            for range in ranges {
+                let count = range.matching_ids(args.pattern.as_ref()).count();
+                if args.per_range {
+                    writeln!(out, "{range} {count}")?;
+                }
+                total += count;
+            }
+            writeln!(out, "{}{total}", if args.per_range { "total " } else { "" })?;
+        }
+        Output::Sum if args.per_range => {
+            let mut sums = Vec::new();
+            for (range, sum) in ranges.iter().zip(sum_ranges(
+                ranges,
+                args.pattern.as_ref(),
+                args.enumerate,
+                args.jobs,
+            )) {
+                let sum = sum?;
+                writeln!(out, "{range} {sum}")?;
+                sums.push(sum);
+            }
+            writeln!(out, "total {}", checked_sum(sums)?)?;
+        }
+        Output::Sum => writeln!(
+            out,
+            "{}",
+            solve(ranges, args.pattern.as_ref(), args.enumerate, args.jobs)?
+        )?,
+    }
+    Ok(())
+}
+
+fn run<T: Id>(args: &Args) {
+    let mut out = std::io::stdout().lock();
+    let result = if args.check {
+        check::<T>(std::io::stdin().lock(), args, &mut out).map_err(Into::into)
+    } else {
+        let data = std::fs::read_to_string(args.filename.as_ref().expect("filename"))
+            .expect("file exists");
+        parse::<T>(&data, args.radix)
+            .map_err(Into::into)
+            .and_then(|ranges| write_output(&merge(ranges), args, &mut out))
+    };
+    if let Err(error) = result {
+        eprintln!("{error}");
+        std::process::exit(1)
+    }
+}
+
+fn main() {
+    let args = Args::parse(std::env::args().skip(1));
+    match args.width {
+        64 => run::<u64>(&args),
+        128 => run::<u128>(&args),
+        width => panic!("Invalid width: {width}"),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::*;
 
+    type Range = crate::Range<u64>;
+    type Group = crate::Group<u64>;
+
     #[test]
     fn test_group_988() {
         {
-            let mut group = Group::from(998, 2);
+            let mut group = Group::from(998, 2, 10);
             assert_eq!(group.value, 1010);
             group.next();
             assert_eq!(group.value, 1111);
@@ -141,13 +882,13 @@ mod tests {
         }
 
         {
-            let mut group = Group::from(998, 3);
+            let mut group = Group::from(998, 3, 10);
             assert_eq!(group.value, 999);
             group.next();
             assert_eq!(group.value, 101010);
         }
         {
-            let mut group = Group::from(998, 4);
+            let mut group = Group::from(998, 4, 10);
             assert_eq!(group.value, 1111);
             group.next();
             assert_eq!(group.value, 2222);
@@ -157,35 +898,35 @@ mod tests {
     #[test]
     fn test_group_200000() {
         {
-            let mut group = Group::from(200_000, 2);
+            let mut group = Group::from(200_000, 2, 10);
             assert_eq!(group.value, 200_200);
             group.next();
             assert_eq!(group.value, 201_201);
         }
 
         {
-            let mut group = Group::from(200_000, 3);
+            let mut group = Group::from(200_000, 3, 10);
             assert_eq!(group.value, 202_020);
             group.next();
             assert_eq!(group.value, 212_121);
         }
 
         {
-            let mut group = Group::from(200_000, 4);
+            let mut group = Group::from(200_000, 4, 10);
             assert_eq!(group.value, 10_101_010);
             group.next();
             assert_eq!(group.value, 11_111_111);
         }
 
         {
-            let mut group = Group::from(200_000, 5);
+            let mut group = Group::from(200_000, 5, 10);
             assert_eq!(group.value, 1_010_101_010);
             group.next();
             assert_eq!(group.value, 1_111_111_111);
         }
 
         {
-            let mut group = Group::from(200_000, 6);
+            let mut group = Group::from(200_000, 6, 10);
             assert_eq!(group.value, 222_222);
             group.next();
             assert_eq!(group.value, 333_333);
@@ -194,13 +935,13 @@ mod tests {
 
     #[test]
     fn test_group_95() {
-        assert_eq!(Group::from(95, 2).value, 99);
-        assert_eq!(Group::from(95, 3).value, 111);
+        assert_eq!(Group::from(95, 2, 10).value, 99);
+        assert_eq!(Group::from(95, 3, 10).value, 111);
     }
 
     #[test]
     fn test_weird() {
-        let mut group = Group::from(999_999, 2);
+        let mut group = Group::from(999_999, 2, 10);
         assert_eq!(group.value, 999_999);
         group.next();
         assert_eq!(group.value, 10_001_000);
@@ -208,38 +949,636 @@ mod tests {
 
     #[test]
     fn test_range() {
-        assert_eq!(Range { start: 11, end: 22 }.sum_invalid_ids(), 11 + 22);
+        assert_eq!(
+            Range {
+                start: 11,
+                end: 22,
+                radix: 10
+            }
+            .sum_invalid_ids(&Rule::AtLeastTwice),
+            Ok(11 + 22)
+        );
         assert_eq!(
             Range {
                 start: 95,
-                end: 115
+                end: 115,
+                radix: 10,
             }
-            .sum_invalid_ids(),
-            99 + 111
+            .sum_invalid_ids(&Rule::AtLeastTwice),
+            Ok(99 + 111)
         );
         assert_eq!(
             Range {
                 start: 998,
-                end: 1012
+                end: 1012,
+                radix: 10,
             }
-            .sum_invalid_ids(),
-            999 + 1010
+            .sum_invalid_ids(&Rule::AtLeastTwice),
+            Ok(999 + 1010)
         );
         assert_eq!(
             Range {
                 start: 1188511880,
-                end: 1188511890
+                end: 1188511890,
+                radix: 10,
             }
-            .sum_invalid_ids(),
-            1188511885
+            .sum_invalid_ids(&Rule::AtLeastTwice),
+            Ok(1188511885)
         );
         assert_eq!(
             Range {
                 start: 2121212118,
-                end: 2121212124
+                end: 2121212124,
+                radix: 10,
+            }
+            .sum_invalid_ids(&Rule::AtLeastTwice),
+            Ok(2121212121)
+        );
+    }
+
+    #[test]
+    fn test_zero_start() {
+        for (end, ids) in [(0, vec![]), (22, vec![11, 22])] {
+            let range = Range {
+                start: 0,
+                end,
+                radix: 10,
+            };
+            assert_eq!(
+                range.invalid_ids(&Rule::AtLeastTwice).collect::<Vec<_>>(),
+                ids
+            );
+            assert_eq!(
+                range.sum_invalid_ids_enumerated(&Rule::AtLeastTwice),
+                range.sum_invalid_ids(&Rule::AtLeastTwice)
+            );
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(Group::get_n_digits(u64::MAX, 10), 20);
+        // 1844674408_1844674408 doesn't fit in u64
+        let mut group = Group::from(18_446_744_070_000_000_000, 2, 10);
+        assert_eq!(group.value, 18_446_744_071_844_674_407);
+        group.next();
+        assert!(group.exhausted);
+
+        let range = Range {
+            start: 18_446_744_000_000_000_000,
+            end: u64::MAX,
+            radix: 10,
+        };
+        for rule in [Rule::Twice, Rule::AtLeastTwice] {
+            assert_eq!(range.sum_invalid_ids(&rule), Err(Overflow { bits: 64 }));
+            assert_eq!(
+                range.sum_invalid_ids_enumerated(&rule),
+                Err(Overflow { bits: 64 })
+            );
+        }
+        assert_eq!(
+            Range {
+                start: 18_446_744_071_000_000_000,
+                end: u64::MAX,
+                radix: 10,
+            }
+            .sum_invalid_ids(&Rule::Twice),
+            Ok(18_446_744_071_844_674_407)
+        );
+
+        // 11111111111111111111 is added for both blocks of 10 and 4 before blocks of 2 remove it
+        let ones = Range {
+            start: 11_111_111_111_111_111_111,
+            end: 11_111_111_111_111_111_111,
+            radix: 10,
+        };
+        assert_eq!(
+            ones.sum_invalid_ids(&Rule::AtLeastTwice),
+            Ok(11_111_111_111_111_111_111)
+        );
+        assert_eq!(
+            ones.sum_invalid_ids(&Rule::AtLeastTwice),
+            ones.sum_invalid_ids_enumerated(&Rule::AtLeastTwice)
+        );
+
+        let wide = crate::Range::<u128> {
+            start: range.start as u128,
+            end: range.end as u128,
+            radix: 10,
+        };
+        for rule in [Rule::Twice, Rule::AtLeastTwice] {
+            assert_eq!(
+                wide.sum_invalid_ids(&rule),
+                wide.sum_invalid_ids_enumerated(&rule)
+            );
+        }
+        // 20 digits IDs made of 2 blocks of 10 digits, blocks have to be <= 1844674407
+        let last = 1_844_674_407_u128;
+        assert_eq!(
+            crate::Range::<u128> {
+                start: 1,
+                end: u64::MAX as u128,
+                radix: 10,
+            }
+            .sum_invalid_ids(&Rule::Twice),
+            Ok((1..=10)
+                .map(|n| {
+                    let first = 10_u128.pow(n - 1);
+                    let last = if n == 10 { last } else { 10_u128.pow(n) - 1 };
+                    (10_u128.pow(n) + 1) * (first + last) * (last - first + 1) / 2
+                })
+                .sum())
+        );
+        assert_eq!(
+            crate::Range::<u128> {
+                start: 1,
+                end: u128::MAX,
+                radix: 10,
             }
-            .sum_invalid_ids(),
-            2121212121
+            .sum_invalid_ids(&Rule::AtLeastTwice),
+            Err(Overflow { bits: 128 })
+        );
+    }
+
+    #[test]
+    fn test_radix() {
+        assert_eq!(Group::get_n_digits(0xfff, 16), 3);
+        let mut group = Group::from(0xfe, 2, 16);
+        assert_eq!(group.value, 0xff);
+        group.next();
+        assert_eq!(group.value, 0x1010);
+
+        assert_eq!(
+            solve_both(&parse("a-ff", 16).unwrap(), &Rule::AtLeastTwice),
+            0x11 * (1..16).sum::<u64>()
+        );
+        // 11, 111, 1010, 1111
+        assert_eq!(
+            solve_both(&parse("1-1111", 2).unwrap(), &Rule::AtLeastTwice),
+            35
+        );
+        assert_eq!(solve_both(&parse("1-1111", 2).unwrap(), &Rule::Twice), 28);
+        assert_eq!(
+            solve_both(&parse("z0-zz", 36).unwrap(), &Rule::Twice),
+            35 * 36 + 35
+        );
+
+        for radix in [2, 3, 7, 16, 36] {
+            for rule in [Rule::Twice, Rule::AtLeastTwice, Rule::Counts(vec![3, 5])] {
+                solve_both(
+                    &[Range {
+                        start: 1,
+                        end: 200_000,
+                        radix,
+                    }],
+                    &rule,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_ids() {
+        let range = Range {
+            start: 95,
+            end: 1_111_112,
+            radix: 10,
+        };
+        let ids = range.invalid_ids(&Rule::AtLeastTwice).collect::<Vec<_>>();
+        assert_eq!(&ids[..5], &[99, 111, 222, 333, 444]);
+        assert_eq!(ids.last(), Some(&1_111_111));
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            range.sum_invalid_ids(&Rule::AtLeastTwice),
+            Ok(ids.iter().sum())
+        );
+        assert_eq!(
+            range
+                .invalid_ids(&Rule::Counts(vec![3, 6]))
+                .take(4)
+                .collect::<Vec<_>>(),
+            vec![111, 222, 333, 444]
+        );
+        let range = Range {
+            start: 222_220,
+            end: 222_224,
+            radix: 10,
+        };
+        assert_eq!(
+            range.invalid_ids(&Rule::AtLeastTwice).collect::<Vec<_>>(),
+            vec![222_222]
+        );
+    }
+
+    #[test]
+    fn test_output() {
+        let output = |data: &str, radix, extra: &[&str]| {
+            let args = Args::parse(["input"].iter().chain(extra).map(|arg| arg.to_string()));
+            let mut out = Vec::new();
+            write_output(&parse::<u64>(data, radix).unwrap(), &args, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(output("11-22,95-115", 10, &[]), "243\n");
+        assert_eq!(output("11-22,95-115", 10, &["--count"]), "4\n");
+        assert_eq!(output("11-22,95-115", 10, &["--list"]), "11\n22\n99\n111\n");
+        assert_eq!(
+            output("11-22,95-115", 10, &["--per-range"]),
+            "11-22 33\n95-115 210\ntotal 243\n"
+        );
+        assert_eq!(
+            output("11-22,95-115", 10, &["--count", "--per-range"]),
+            "11-22 2\n95-115 2\ntotal 4\n"
+        );
+        assert_eq!(
+            output("f0-110", 16, &["--list", "--per-range", "--part", "1"]),
+            "f0-110 ff\n"
+        );
+        assert_eq!(format_radix(0_u64, 2), "0");
+        assert_eq!(format_radix(1295_u64, 36), "zz");
+    }
+
+    #[test]
+    fn test_is_invalid() {
+        let rule = Rule::AtLeastTwice;
+        assert_eq!(
+            is_invalid(824824824_u64, 10, &rule),
+            Some(("824".to_string(), 3))
+        );
+        assert_eq!(
+            is_invalid(111111_u64, 10, &rule),
+            Some(("1".to_string(), 6))
+        );
+        assert_eq!(
+            is_invalid(111111_u64, 10, &Rule::Twice),
+            Some(("111".to_string(), 2))
+        );
+        assert_eq!(
+            is_invalid(111111_u64, 10, &Rule::Counts(vec![2, 3])),
+            Some(("11".to_string(), 3))
+        );
+        assert_eq!(is_invalid(824824824_u64, 10, &Rule::Twice), None);
+        assert_eq!(is_invalid(7_u64, 10, &rule), None);
+        assert_eq!(is_invalid(1234_u64, 10, &rule), None);
+        assert_eq!(
+            is_invalid(0xabab_u64, 16, &rule),
+            Some(("ab".to_string(), 2))
+        );
+
+        // Agrees with the ranges
+        let range = Range {
+            start: 1,
+            end: 200_000,
+            radix: 10,
+        };
+        let mut ids = range.invalid_ids(&rule).peekable();
+        for id in range.start..=range.end {
+            let expected = ids.next_if_eq(&id).is_some();
+            assert_eq!(is_invalid(id, 10, &rule).is_some(), expected, "{id}");
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let args = Args::parse(["--check".to_string()].into_iter());
+        let mut out = Vec::new();
+        check::<u64>("824824824\n\n 123 \n12x\n".as_bytes(), &args, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "824824824 invalid 824 3\n123 valid\n12x error invalid digit found in string\n"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let ranges = parse::<u64>(" 11-22,\n95 - 115,\r\n\n998-1012,", 10).unwrap();
+        assert_eq!(
+            ranges
+                .iter()
+                .map(|range| (range.start, range.end))
+                .collect::<Vec<_>>(),
+            vec![(11, 22), (95, 115), (998, 1012)]
+        );
+
+        let error = |data| parse::<u64>(data, 10).unwrap_err();
+        assert_eq!(
+            error("11-22,\n 95-1x5"),
+            ParseError {
+                line: 2,
+                column: 5,
+                text: "1x5".to_string(),
+                kind: ParseErrorKind::Number("1x5".parse::<u64>().unwrap_err()),
+            }
+        );
+        assert_eq!(
+            error("11-22,  95"),
+            ParseError {
+                line: 1,
+                column: 9,
+                text: "95".to_string(),
+                kind: ParseErrorKind::Dash,
+            }
+        );
+        assert_eq!(
+            error("é-é, 22-11").to_string(),
+            "line 1, column 1: invalid number (invalid digit found in string): \"é\""
+        );
+        assert_eq!(
+            error("1-2, 22-11").to_string(),
+            "line 1, column 6: range start is greater than its end: \"22-11\""
+        );
+        assert_eq!(
+            error("1-").kind,
+            ParseErrorKind::Number("".parse::<u64>().unwrap_err())
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let merged = merge(parse::<u64>("50-60,1-10,5-20,21-30,55-58,40-45", 10).unwrap());
+        assert_eq!(
+            merged
+                .iter()
+                .map(|range| (range.start, range.end))
+                .collect::<Vec<_>>(),
+            vec![(1, 30), (40, 45), (50, 60)]
+        );
+        let ranges = parse::<u64>("11-1000,95-115,990-1012,95-115", 10).unwrap();
+        assert_eq!(
+            solve_both(&merge(ranges), &Rule::AtLeastTwice),
+            solve_both(&parse("11-1012", 10).unwrap(), &Rule::AtLeastTwice)
+        );
+        let merged = merge(vec![
+            Range {
+                start: 5,
+                end: u64::MAX,
+                radix: 10,
+            },
+            Range {
+                start: u64::MAX,
+                end: u64::MAX,
+                radix: 10,
+            },
+        ]);
+        assert_eq!(merged.len(), 1);
+    }
+
+    #[test]
+    fn test_parallel() {
+        let ranges = merge(
+            (1..500_u64)
+                .map(|ind| Range {
+                    start: ind * ind * 997,
+                    end: ind * ind * 997 + ind * 4_001,
+                    radix: 10,
+                })
+                .collect(),
+        );
+        for rule in [Rule::Twice, Rule::AtLeastTwice] {
+            let sequential = solve(&ranges, &rule, false, 1);
+            let sums = sum_ranges(&ranges, &rule, false, 1);
+            for jobs in [2, 3, 8, 1000] {
+                assert_eq!(solve(&ranges, &rule, false, jobs), sequential);
+                assert_eq!(sum_ranges(&ranges, &rule, false, jobs), sums);
+            }
+            assert_eq!(solve(&ranges, &rule, true, 4), sequential);
+        }
+        assert_eq!(
+            solve(&parse::<u64>(EXAMPLE, 10).unwrap(), &Rule::Twice, false, 4),
+            Ok(1227775554)
+        );
+
+        let overflow = [
+            Range {
+                start: 11,
+                end: 22,
+                radix: 10,
+            },
+            Range {
+                start: 18_446_744_000_000_000_000,
+                end: u64::MAX,
+                radix: 10,
+            },
+        ];
+        assert_eq!(
+            solve(&overflow, &Rule::Twice, false, 2),
+            Err(Overflow { bits: 64 })
+        );
+    }
+
+    // Reference: every ID of the range made of size blocks, checked from its digits
+    fn brute_force(range: &Range, size: u32) -> Vec<u64> {
+        (range.start..=range.end)
+            .filter(|&id| {
+                let digits = format_radix(id, range.radix);
+                if !digits.len().is_multiple_of(size as usize) {
+                    return false;
+                }
+                let mut blocks = digits.as_bytes().chunks(digits.len() / size as usize);
+                let first = blocks.next();
+                blocks.all(|block| Some(block) == first)
+            })
+            .collect()
+    }
+
+    // xorshift64*, good enough to generate test cases
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn range(&mut self, start: u64, end: u64) -> u64 {
+            start + self.next() % (end - start)
+        }
+    }
+
+    // Walks the group through the range, panics with its state on the first value that
+    // differs from the reference
+    fn check_group(range: &Range, size: u32) {
+        let expected = brute_force(range, size);
+        let mut group = range.get_group(size);
+        for (ind, &value) in expected.iter().enumerate() {
+            assert!(
+                !group.exhausted && group.value == value,
+                "{range:?} size = {size}: item {ind} is {} instead of {value}, {group:?}",
+                group.value
+            );
+            group.next();
+        }
+        assert!(
+            group.exhausted || group.value > range.end,
+            "{range:?} size = {size}: extra item {}, {group:?}",
+            group.value
+        );
+    }
+
+    #[test]
+    fn test_group_brute_force() {
+        let mut rng = Rng(0xda72);
+        for _ in 0..1000 {
+            let radix = [2, 3, 10, 10, 10, 16][rng.range(0, 6) as usize];
+            let n_digits = rng.range(1, 8) as u32;
+            let start = rng.range(1, (radix as u64).pow(n_digits));
+            let range = Range {
+                start,
+                end: start + rng.range(0, 1000),
+                radix,
+            };
+            for size in 2..=Group::get_n_digits(range.end, radix) {
+                check_group(&range, size);
+            }
+        }
+    }
+
+    #[test]
+    fn test_patterns() {
+        assert!(Palindrome.is_match("12321"));
+        assert!(Palindrome.is_match("7"));
+        assert!(!Palindrome.is_match("1232"));
+        assert!(HalfPrefix.is_match("123123"));
+        assert!(HalfPrefix.is_match("1231234"));
+        assert!(!HalfPrefix.is_match("1232134"));
+        assert!(!HalfPrefix.is_match("1"));
+        let pattern = BlockThenSuffix { min_repeats: 3 };
+        assert!(pattern.is_match("1212125"));
+        assert!(pattern.is_match("111"));
+        assert!(pattern.is_match("1112345"));
+        assert!(!pattern.is_match("1212345"));
+        assert!(!pattern.is_match("11"));
+        assert!(Rule::Twice.is_match("123123"));
+        assert!(!Rule::Twice.is_match("123123123"));
+        assert!(parse_pattern("block-suffix:2").is_match("11234"));
+
+        let range = Range {
+            start: 95,
+            end: 12_345,
+            radix: 10,
+        };
+        let palindromes = range.matching_ids(&Palindrome).collect::<Vec<_>>();
+        assert_eq!(&palindromes[..4], &[99, 101, 111, 121]);
+        assert_eq!(palindromes.len(), 1 + 90 + 90 + 24);
+        assert_eq!(
+            range.sum_matching(&Palindrome, false),
+            Ok(palindromes.iter().sum())
+        );
+        assert_eq!(
+            range.matching_ids(&HalfPrefix).take(4).collect::<Vec<_>>(),
+            vec![99, 110, 111, 112]
+        );
+    }
+
+    // Hides as_rule so that every ID is checked
+    #[derive(Debug)]
+    struct Scan(Rule);
+
+    impl Pattern for Scan {
+        fn is_match(&self, digits: &str) -> bool {
+            self.0.is_match(digits)
+        }
+    }
+
+    #[test]
+    fn test_pattern_scan() {
+        let ranges = parse::<u64>("1-20000,998-1012,1188511880-1188511890", 10).unwrap();
+        for rule in [Rule::Twice, Rule::AtLeastTwice, Rule::Counts(vec![3])] {
+            assert_eq!(
+                solve(&ranges, &Scan(rule.clone()), false, 2),
+                solve(&ranges, &rule, false, 1)
+            );
+            for range in &ranges {
+                assert!(
+                    range
+                        .matching_ids(&Scan(rule.clone()))
+                        .eq(range.invalid_ids(&rule))
+                );
+            }
+        }
+    }
+
+    const EXAMPLE: &str = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,\
+1698522-1698528,446443-446449,38593856-38593862,565653-565659,\
+824824821-824824827,2121212118-2121212124";
+
+    // Checks the closed form against the enumeration
+    fn solve_both(ranges: &[Range], rule: &Rule) -> u64 {
+        let sum = solve(ranges, rule, false, 1).unwrap();
+        assert_eq!(Ok(sum), solve(ranges, rule, true, 1), "{ranges:?} {rule:?}");
+        sum
+    }
+
+    #[test]
+    fn test_mobius() {
+        assert_eq!(
+            (1..=12).map(mobius).collect::<Vec<_>>(),
+            vec![1, -1, -1, 0, -1, 1, -1, 0, 0, 1, -1, 0]
+        );
+    }
+
+    #[test]
+    fn test_closed_form() {
+        let rules = [
+            Rule::Twice,
+            Rule::AtLeastTwice,
+            Rule::Counts(vec![3]),
+            Rule::Counts(vec![2, 3]),
+            Rule::Counts(vec![4, 6]),
+        ];
+        for rule in &rules {
+            solve_both(
+                &[Range {
+                    start: 1,
+                    end: 1_000_000,
+                    radix: 10,
+                }],
+                rule,
+            );
+            solve_both(
+                &[Range {
+                    start: 123_456,
+                    end: 98_765_432,
+                    radix: 10,
+                }],
+                rule,
+            );
+        }
+        // Too wide to enumerate quickly
+        assert_eq!(
+            Range {
+                start: 1,
+                end: 9_999_999_999,
+                radix: 10,
+            }
+            .sum_invalid_ids(&Rule::Twice),
+            Ok((1..=5)
+                .map(|n| {
+                    let (first, last) = (10_u64.pow(n - 1), 10_u64.pow(n) - 1);
+                    (10_u64.pow(n) + 1) * (first + last) * (last - first + 1) / 2
+                })
+                .sum::<u64>())
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let ranges = parse(EXAMPLE, 10).unwrap();
+        assert_eq!(solve_both(&ranges, &Rule::Twice), 1227775554);
+        assert_eq!(solve_both(&ranges, &Rule::AtLeastTwice), 4174379265);
+        assert_eq!(
+            solve_both(&ranges, &Rule::Counts((2..=10).collect())),
+            4174379265
+        );
+        assert_eq!(solve_both(&ranges, &Rule::Counts(vec![2])), 1227775554);
+        // 2121212121 has 5 blocks
+        assert_eq!(
+            solve_both(&ranges, &Rule::Counts(vec![3])),
+            111 + 999 + 222222 + 565656 + 824824824
         );
+        assert_eq!(solve_both(&ranges, &Rule::Counts(vec![11])), 0);
+        assert_eq!(solve_both(&ranges, &Rule::Counts(vec![0, 1])), 0);
+        assert_eq!(solve_both(&ranges, &Rule::Counts(vec![1, 2])), 1227775554);
     }
 }
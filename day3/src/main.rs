@@ -1,13 +1,102 @@
+use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Lines};
 
 type Joltage = u64;
+// Any k digits fit up to this, 20 nines don't fit in a u64
+const MAX_SELECTED: usize = Joltage::MAX.ilog10() as usize;
+
+#[derive(Debug, PartialEq)]
+enum JoltageError {
+    // k == 0
+    NothingSelected,
+    // Number of selected batteries
+    TooManySelected(usize),
+    // Bank has fewer batteries than selected
+    TooShort { len: usize, k: usize },
+    // Windows have fewer batteries than selected
+    WindowTooSmall { window: usize, k: usize },
+    // Only zeros can lead the selection when leading zeros are forbidden
+    LeadingZero,
+    // Sum over all banks
+    Overflow,
+}
+
+impl fmt::Display for JoltageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JoltageError::NothingSelected => write!(f, "must select at least 1 battery"),
+            JoltageError::TooManySelected(k) => write!(
+                f,
+                "can't select {k} batteries, joltage only fits for up to {MAX_SELECTED}"
+            ),
+            JoltageError::TooShort { len, k } => {
+                write!(f, "can't select {k} batteries out of {len}")
+            }
+            JoltageError::WindowTooSmall { window, k } => {
+                write!(f, "can't select {k} batteries from windows of {window}")
+            }
+            JoltageError::LeadingZero => write!(f, "no nonzero battery can lead the selection"),
+            JoltageError::Overflow => {
+                write!(f, "total joltage doesn't fit in {} bits", Joltage::BITS)
+            }
+        }
+    }
+}
+
+impl std::error::Error for JoltageError {}
+
 #[derive(Debug)]
 struct Bank {
     batteries: Vec<Joltage>,
 }
 
-type BankPower = [Joltage; 12];
+// Selected digits, most significant first
+type BankPower = Vec<Joltage>;
+
+#[derive(Debug, PartialEq)]
+struct Selection {
+    joltage: Joltage,
+    // Positions of the selected batteries in the bank, ascending
+    indices: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Objective {
+    Largest,
+    Smallest,
+}
+
+impl Objective {
+    // Whether battery a is preferred over b
+    fn beats(self, a: Joltage, b: Joltage) -> bool {
+        match self {
+            Objective::Largest => a > b,
+            Objective::Smallest => a < b,
+        }
+    }
+}
+
+// How the batteries of a bank are selected
+#[derive(Debug, Clone, Copy)]
+struct Strategy {
+    objective: Objective,
+    // First selected battery can't be 0
+    no_leading_zero: bool,
+    // Selects from consecutive windows of this size and sums their joltage, a trailing
+    // window too short for the selection is ignored
+    window: Option<usize>,
+}
+
+impl Default for Strategy {
+    fn default() -> Self {
+        Strategy {
+            objective: Objective::Largest,
+            no_leading_zero: false,
+            window: None,
+        }
+    }
+}
 
 fn compute_power(bank_power: &BankPower) -> Joltage {
     bank_power
@@ -20,32 +109,159 @@ fn compute_power(bank_power: &BankPower) -> Joltage {
 }
 
 impl Bank {
+    // pos is the number of batteries selected so far
     fn recurse(values: &[Joltage], mut bank_power: BankPower, best: &mut Joltage, mut pos: usize) {
         if values.is_empty() {
             return;
         }
         let first = values[0];
         let n1 = bank_power.len() - 1;
-        for next_pos in 0..=pos {
+        for next_pos in 0..=pos.min(n1) {
             if n1 - next_pos > values.len() - 1 {
                 continue;
             }
-            if first > bank_power[next_pos] {
+            // An empty slot takes any battery, including 0
+            if next_pos == pos || first > bank_power[next_pos] {
                 bank_power[next_pos] = first;
                 bank_power[next_pos + 1..].fill(0);
                 if next_pos == n1 {
                     *best = std::cmp::max(*best, compute_power(&bank_power));
                 }
-                pos = std::cmp::min(next_pos + 1, n1);
+                pos = next_pos + 1;
                 break;
             }
         }
         Bank::recurse(&values[1..], bank_power, best, pos)
     }
 
-    fn get_largest_joltage(&self) -> Joltage {
+    // Best k batteries of range kept in order: a battery evicts the ones before it that it
+    // beats while enough batteries remain to fill the selection
+    fn select_ordered(
+        &self,
+        range: std::ops::Range<usize>,
+        k: usize,
+        objective: Objective,
+    ) -> Vec<usize> {
+        let mut drops = range.len() - k;
+        let mut selected: Vec<usize> = Vec::with_capacity(k);
+        for ind in range {
+            let battery = self.batteries[ind];
+            while drops > 0
+                && selected
+                    .last()
+                    .is_some_and(|&last| objective.beats(battery, self.batteries[last]))
+            {
+                selected.pop();
+                drops -= 1;
+            }
+            if selected.len() < k {
+                selected.push(ind);
+            } else {
+                drops -= 1;
+            }
+        }
+        selected
+    }
+
+    fn power(&self, indices: &[usize]) -> BankPower {
+        indices.iter().map(|&ind| self.batteries[ind]).collect()
+    }
+
+    // Bank and joltage, with the selected batteries marked underneath
+    fn render(&self, selection: &Selection) -> String {
+        let mut marks = vec![' '; self.batteries.len()];
+        for &ind in &selection.indices {
+            marks[ind] = '^';
+        }
+        format!(
+            "{} | {}\n{}",
+            self.batteries
+                .iter()
+                .map(|digit| digit.to_string())
+                .collect::<String>(),
+            selection.joltage,
+            marks.into_iter().collect::<String>().trim_end()
+        )
+    }
+
+    // A bank of exactly k batteries selects all of them
+    fn check_selection(&self, k: usize) -> Result<(), JoltageError> {
+        if k == 0 {
+            return Err(JoltageError::NothingSelected);
+        }
+        if k > MAX_SELECTED {
+            return Err(JoltageError::TooManySelected(k));
+        }
+        if self.batteries.len() < k {
+            return Err(JoltageError::TooShort {
+                len: self.batteries.len(),
+                k,
+            });
+        }
+        Ok(())
+    }
+
+    // Best k batteries of range (at least k long) kept in order, leading with the leftmost
+    // best nonzero battery that leaves enough batteries after it if needed
+    fn select_range(
+        &self,
+        range: std::ops::Range<usize>,
+        k: usize,
+        strategy: &Strategy,
+    ) -> Result<Vec<usize>, JoltageError> {
+        if !strategy.no_leading_zero {
+            return Ok(self.select_ordered(range, k, strategy.objective));
+        }
+        let first = (range.start..=range.end - k)
+            .filter(|&ind| self.batteries[ind] != 0)
+            .reduce(|best, ind| {
+                if strategy
+                    .objective
+                    .beats(self.batteries[ind], self.batteries[best])
+                {
+                    ind
+                } else {
+                    best
+                }
+            })
+            .ok_or(JoltageError::LeadingZero)?;
+        let mut indices = vec![first];
+        indices.extend(self.select_ordered(first + 1..range.end, k - 1, strategy.objective));
+        Ok(indices)
+    }
+
+    // Number made of k batteries kept in order, and their positions. With windows, the
+    // joltage is the sum over windows
+    fn select(&self, k: usize, strategy: &Strategy) -> Result<Selection, JoltageError> {
+        self.check_selection(k)?;
+        let window = strategy.window.unwrap_or(self.batteries.len());
+        if window < k {
+            return Err(JoltageError::WindowTooSmall { window, k });
+        }
+        let mut selection = Selection {
+            joltage: 0,
+            indices: Vec::new(),
+        };
+        for start in (0..self.batteries.len()).step_by(window) {
+            let end = (start + window).min(self.batteries.len());
+            if end - start < k {
+                break;
+            }
+            let indices = self.select_range(start..end, k, strategy)?;
+            selection.joltage = selection
+                .joltage
+                .checked_add(compute_power(&self.power(&indices)))
+                .ok_or(JoltageError::Overflow)?;
+            selection.indices.extend(indices);
+        }
+        Ok(selection)
+    }
+
+    // Largest joltage of the whole bank like the default strategy, recursing once per battery
+    fn get_largest_joltage_recursive(&self, k: usize) -> Result<Joltage, JoltageError> {
+        self.check_selection(k)?;
         // Microoptimization
-        let max = self.batteries[..self.batteries.len() - 12]
+        let max = self.batteries[..self.batteries.len() - k + 1]
             .iter()
             .max()
             .expect(">= 1 battery");
@@ -54,42 +270,582 @@ impl Bank {
             .iter()
             .position(|battery| battery == max)
             .expect("1 battery matches max");
-        let mut bank_power = BankPower::default();
+        let mut bank_power = vec![0; k];
         bank_power[0] = *max;
 
-        let mut best = 0;
+        // Already complete for k == 1
+        let mut best = if k == 1 { *max } else { 0 };
         Bank::recurse(&self.batteries[pos + 1..], bank_power, &mut best, 1);
-        println!("{self:?} | {best}");
-        best
+        Ok(best)
     }
 }
 
 type FileLines = Lines<BufReader<File>>;
-fn parse(lines: FileLines) -> impl Iterator<Item = Bank> {
-    lines.into_iter().map(|line| {
+// Banks with their line number
+fn parse(lines: FileLines) -> impl Iterator<Item = (usize, Bank)> {
+    lines.into_iter().zip(1..).map(|(line, ind)| {
         let line = line.expect("line is valid");
-        Bank {
+        let bank = Bank {
             batteries: line
                 .chars()
                 .map(|c| c.to_digit(10).expect("battery has a valid value") as Joltage)
                 .collect(),
-        }
+        };
+        (ind, bank)
     })
 }
 
+// Line number and error of each bank left out of the total
+type Skipped = Vec<(usize, JoltageError)>;
+
+// Banks without a valid selection are skipped, other errors stop the run. explain gets the
+// line number, bank and selection of the others, except with the recursive solver
+fn solve(
+    banks: impl Iterator<Item = (usize, Bank)>,
+    k: usize,
+    strategy: &Strategy,
+    recursive: bool,
+    mut explain: impl FnMut(usize, &Bank, &Selection),
+) -> Result<(Joltage, Skipped), JoltageError> {
+    let mut total: Joltage = 0;
+    let mut skipped = Skipped::new();
+    for (ind, bank) in banks {
+        let joltage = if recursive {
+            bank.get_largest_joltage_recursive(k)
+        } else {
+            bank.select(k, strategy).map(|selection| {
+                explain(ind, &bank, &selection);
+                selection.joltage
+            })
+        };
+        match joltage {
+            Ok(joltage) => total = total.checked_add(joltage).ok_or(JoltageError::Overflow)?,
+            Err(e @ (JoltageError::TooShort { .. } | JoltageError::LeadingZero)) => {
+                skipped.push((ind, e))
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((total, skipped))
+}
+
+#[derive(Debug)]
+struct Args {
+    filename: String,
+    // Number of batteries selected per bank
+    k: usize,
+    strategy: Strategy,
+    // Uses Bank::recurse instead of the linear selection, largest joltage only
+    recursive: bool,
+    // Prints the selected batteries of each bank
+    explain: bool,
+}
+
+impl Args {
+    // Usage: day3 <filename> [--part 1|2] [--select K] [--smallest] [--no-leading-zero]
+    //             [--window W] [--recursive] [--explain]
+    // --recursive takes a stack frame per battery, it's only safe for short banks (a bank of
+    // 10^6 batteries overflows the main thread stack)
+    fn parse(mut args: impl Iterator<Item = String>) -> Self {
+        let mut filename = None;
+        let mut k = 12;
+        let mut strategy = Strategy::default();
+        let mut recursive = false;
+        let mut explain = false;
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .unwrap_or_else(|| panic!("{arg} expects a value"))
+            };
+            match arg.as_str() {
+                "--part" => {
+                    k = match value().as_str() {
+                        "1" => 2,
+                        "2" => 12,
+                        part => panic!("Invalid part: {part}"),
+                    }
+                }
+                "--select" => {
+                    k = match value().parse() {
+                        Ok(k) if k > 0 => k,
+                        _ => panic!("Invalid selection, must be > 0"),
+                    }
+                }
+                "--smallest" => strategy.objective = Objective::Smallest,
+                "--no-leading-zero" => strategy.no_leading_zero = true,
+                "--window" => {
+                    strategy.window = match value().parse() {
+                        Ok(window) if window > 0 => Some(window),
+                        _ => panic!("Invalid window, must be > 0"),
+                    }
+                }
+                "--recursive" => recursive = true,
+                "--explain" => explain = true,
+                _ if filename.is_none() => filename = Some(arg),
+                _ => panic!("Unexpected argument: {arg}"),
+            }
+        }
+        if recursive
+            && (strategy.objective != Objective::Largest
+                || strategy.no_leading_zero
+                || strategy.window.is_some())
+        {
+            panic!("--recursive only selects the largest joltage of whole banks");
+        }
+        if recursive && explain {
+            panic!("--recursive doesn't report selected batteries, can't --explain");
+        }
+        Args {
+            filename: filename.expect("filename"),
+            k,
+            strategy,
+            recursive,
+            explain,
+        }
+    }
+}
+
 fn main() {
-    println!(
-        "{}",
-        parse(
-            BufReader::new(
-                File::open(std::env::args().nth(1).expect("filename")).expect("file exists"),
-            )
-            .lines()
-        )
-        .map(|bank| bank.get_largest_joltage())
-        .sum::<Joltage>()
-    );
+    let args = Args::parse(std::env::args().skip(1));
+    let banks = parse(BufReader::new(File::open(&args.filename).expect("file exists")).lines());
+    // Skipped banks are reported with the total
+    let explain = |ind, bank: &Bank, selection: &Selection| {
+        if args.explain {
+            println!("line {ind}:\n{}", bank.render(selection));
+        }
+    };
+    match solve(banks, args.k, &args.strategy, args.recursive, explain) {
+        Ok((total, skipped)) => {
+            for (ind, e) in skipped {
+                eprintln!("line {ind}: skipped: {e}");
+            }
+            println!("{total}");
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use crate::*;
+
+    fn bank(batteries: &str) -> Bank {
+        Bank {
+            batteries: batteries
+                .chars()
+                .map(|c| c.to_digit(10).unwrap() as Joltage)
+                .collect(),
+        }
+    }
+
+    impl Bank {
+        fn largest(&self, k: usize) -> Result<Joltage, JoltageError> {
+            self.select(k, &Strategy::default())
+                .map(|selection| selection.joltage)
+        }
+    }
+
+    fn banks<'a>(lines: &'a [&str]) -> impl Iterator<Item = (usize, Bank)> + 'a {
+        lines
+            .iter()
+            .map(|line| bank(line))
+            .enumerate()
+            .map(|(ind, bank)| (ind + 1, bank))
+    }
+
+    const EXAMPLE: [&str; 4] = [
+        "987654321111111",
+        "811111111111119",
+        "234234234234278",
+        "818181911112111",
+    ];
+
+    #[test]
+    fn test_example() {
+        let expected = [
+            (2, [98, 89, 78, 92]),
+            (12, [987654321111, 811111111119, 434234234278, 888911112111]),
+        ];
+        for (k, joltages) in expected {
+            for (batteries, joltage) in EXAMPLE.iter().zip(joltages) {
+                assert_eq!(bank(batteries).largest(k), Ok(joltage));
+            }
+        }
+        assert_eq!(
+            solve(
+                banks(&EXAMPLE),
+                2,
+                &Strategy::default(),
+                false,
+                |_, _, _| {}
+            ),
+            Ok((357, vec![]))
+        );
+        assert_eq!(
+            solve(
+                banks(&EXAMPLE),
+                12,
+                &Strategy::default(),
+                false,
+                |_, _, _| {}
+            ),
+            Ok((3121910778619, vec![]))
+        );
+    }
+
+    #[test]
+    fn test_select() {
+        let bank = bank("2819372645");
+        assert_eq!(bank.largest(1), Ok(9));
+        assert_eq!(bank.largest(3), Ok(976));
+        assert_eq!(bank.largest(9), Ok(819372645));
+        assert_eq!(bank.largest(10), Ok(2819372645));
+    }
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn range(&mut self, start: u64, end: u64) -> u64 {
+            start + self.next() % (end - start)
+        }
+    }
+
+    fn random_bank(rng: &mut Rng, len: usize) -> Bank {
+        Bank {
+            batteries: (0..len).map(|_| rng.range(0, 10)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_recursive() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..2000 {
+            let len = rng.range(1, 40) as usize;
+            let bank = random_bank(&mut rng, len);
+            let k = rng.range(1, len.min(MAX_SELECTED) as u64 + 1) as usize;
+            assert_eq!(
+                bank.largest(k),
+                bank.get_largest_joltage_recursive(k),
+                "{bank:?} k = {k}"
+            );
+        }
+        assert_eq!(bank("9080").get_largest_joltage_recursive(3), Ok(980));
+        assert_eq!(bank("000").get_largest_joltage_recursive(2), Ok(0));
+    }
+
+    // cargo test --release -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_solvers() {
+        let mut rng = Rng(1);
+        let bank = random_bank(&mut rng, 1_000_000);
+        for k in [2, 12] {
+            let now = std::time::Instant::now();
+            let largest = bank.largest(k).unwrap();
+            println!("select k = {k}: {:?}", now.elapsed());
+
+            // One frame per battery doesn't fit the default stack
+            let now = std::time::Instant::now();
+            let recursive = std::thread::scope(|scope| {
+                std::thread::Builder::new()
+                    .stack_size(1 << 30)
+                    .spawn_scoped(scope, || bank.get_largest_joltage_recursive(k))
+                    .unwrap()
+                    .join()
+                    .unwrap()
+            });
+            println!("recursive k = {k}: {:?}", now.elapsed());
+            assert_eq!(recursive, Ok(largest));
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        let bank = bank(&"9".repeat(25));
+        assert_eq!(bank.largest(19), Ok(9_999_999_999_999_999_999));
+        assert_eq!(bank.largest(20), Err(JoltageError::TooManySelected(20)));
+        let banks = [
+            (
+                1,
+                Bank {
+                    batteries: bank.batteries.clone(),
+                },
+            ),
+            (2, bank),
+        ];
+        assert_eq!(
+            solve(
+                banks.into_iter(),
+                19,
+                &Strategy::default(),
+                false,
+                |_, _, _| {}
+            ),
+            Err(JoltageError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_nothing_selected() {
+        let no_leading_zero = Strategy {
+            no_leading_zero: true,
+            ..Strategy::default()
+        };
+        for batteries in ["", "123"] {
+            let bank = bank(batteries);
+            for strategy in [Strategy::default(), no_leading_zero] {
+                assert_eq!(
+                    bank.select(0, &strategy),
+                    Err(JoltageError::NothingSelected)
+                );
+            }
+            assert_eq!(
+                bank.get_largest_joltage_recursive(0),
+                Err(JoltageError::NothingSelected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_short() {
+        for recursive in [false, true] {
+            let get = |batteries: &str, k| {
+                let bank = bank(batteries);
+                if recursive {
+                    bank.get_largest_joltage_recursive(k)
+                } else {
+                    bank.largest(k)
+                }
+            };
+            assert_eq!(get("3141", 4), Ok(3141));
+            assert_eq!(get("314", 4), Err(JoltageError::TooShort { len: 3, k: 4 }));
+            assert_eq!(get("", 1), Err(JoltageError::TooShort { len: 0, k: 1 }));
+
+            let lines = ["987654321111111", "", "12", "811111111111119", "7"];
+            assert_eq!(
+                solve(
+                    banks(&lines),
+                    2,
+                    &Strategy::default(),
+                    recursive,
+                    |_, _, _| {}
+                ),
+                Ok((
+                    98 + 12 + 89,
+                    vec![
+                        (2, JoltageError::TooShort { len: 0, k: 2 }),
+                        (5, JoltageError::TooShort { len: 1, k: 2 })
+                    ]
+                ))
+            );
+            assert_eq!(
+                solve(
+                    banks(&lines),
+                    20,
+                    &Strategy::default(),
+                    recursive,
+                    |_, _, _| {}
+                ),
+                Err(JoltageError::TooManySelected(20))
+            );
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        let bank = bank("818181911112111");
+        let selection = bank.select(12, &Strategy::default()).unwrap();
+        assert_eq!(selection.joltage, 888911112111);
+        assert_eq!(
+            selection.indices,
+            vec![0, 2, 4, 6, 7, 8, 9, 10, 11, 12, 13, 14]
+        );
+        assert_eq!(
+            bank.render(&selection),
+            "818181911112111 | 888911112111\n^ ^ ^ ^^^^^^^^^"
+        );
+        let selection = bank.select(2, &Strategy::default()).unwrap();
+        assert_eq!(selection.indices, vec![6, 11]);
+
+        let mut explained = Vec::new();
+        let total = solve(
+            banks(&EXAMPLE),
+            2,
+            &Strategy::default(),
+            false,
+            |ind, _, selection| explained.push((ind, selection.indices.clone())),
+        );
+        assert_eq!(total, Ok((357, vec![])));
+        assert_eq!(
+            explained,
+            vec![
+                (1, vec![0, 1]),
+                (2, vec![0, 14]),
+                (3, vec![13, 14]),
+                (4, vec![6, 11])
+            ]
+        );
+        assert_eq!(
+            bank.render(&selection),
+            "818181911112111 | 92\n      ^    ^"
+        );
+    }
+
+    // Best over every k-subset of a short bank
+    fn brute_force(bank: &Bank, k: usize, strategy: &Strategy) -> Option<Joltage> {
+        let n = bank.batteries.len();
+        let joltages = (0..1u32 << n)
+            .filter(|mask| mask.count_ones() as usize == k)
+            .map(|mask| {
+                bank.power(
+                    &(0..n)
+                        .filter(|ind| mask >> ind & 1 == 1)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .filter(|power| !strategy.no_leading_zero || power[0] != 0)
+            .map(|power| compute_power(&power));
+        match strategy.objective {
+            Objective::Largest => joltages.max(),
+            Objective::Smallest => joltages.min(),
+        }
+    }
+
+    #[test]
+    fn test_strategies() {
+        let mut rng = Rng(42);
+        for _ in 0..2000 {
+            let len = rng.range(1, 12) as usize;
+            let bank = Bank {
+                batteries: (0..len).map(|_| rng.range(0, 4)).collect(),
+            };
+            let k = rng.range(1, len as u64 + 1) as usize;
+            for objective in [Objective::Largest, Objective::Smallest] {
+                for no_leading_zero in [false, true] {
+                    let strategy = Strategy {
+                        objective,
+                        no_leading_zero,
+                        window: None,
+                    };
+                    assert_eq!(
+                        bank.select(k, &strategy)
+                            .map(|selection| selection.joltage)
+                            .ok(),
+                        brute_force(&bank, k, &strategy),
+                        "{bank:?} k = {k} {strategy:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_smallest() {
+        let smallest = Strategy {
+            objective: Objective::Smallest,
+            ..Strategy::default()
+        };
+        assert_eq!(
+            bank("987654321111111")
+                .select(2, &smallest)
+                .unwrap()
+                .joltage,
+            11
+        );
+        assert_eq!(
+            bank("234234234234278")
+                .select(3, &smallest)
+                .unwrap()
+                .joltage,
+            222
+        );
+        let selection = bank("0501").select(2, &smallest).unwrap();
+        assert_eq!(selection.joltage, 0);
+        assert_eq!(selection.indices, vec![0, 2]);
+
+        let no_leading_zero = Strategy {
+            no_leading_zero: true,
+            ..smallest
+        };
+        let selection = bank("0501").select(2, &no_leading_zero).unwrap();
+        assert_eq!(selection.joltage, 50);
+        assert_eq!(selection.indices, vec![1, 2]);
+        let no_leading_zero = Strategy {
+            no_leading_zero: true,
+            ..Strategy::default()
+        };
+        assert_eq!(bank("0050").largest(2), Ok(50));
+        assert_eq!(bank("0005").largest(2), Ok(5));
+        assert_eq!(
+            bank("0005").select(2, &no_leading_zero),
+            Err(JoltageError::LeadingZero)
+        );
+        assert_eq!(
+            solve(
+                banks(&["0005", "0050"]),
+                2,
+                &no_leading_zero,
+                false,
+                |_, _, _| {}
+            ),
+            Ok((50, vec![(1, JoltageError::LeadingZero)]))
+        );
+    }
+
+    #[test]
+    fn test_window() {
+        let window = Strategy {
+            window: Some(2),
+            ..Strategy::default()
+        };
+        let selection = bank("9119").select(1, &window).unwrap();
+        assert_eq!(selection.joltage, 18);
+        assert_eq!(selection.indices, vec![0, 3]);
+        let selection = bank("9119")
+            .select(
+                1,
+                &Strategy {
+                    objective: Objective::Smallest,
+                    ..window
+                },
+            )
+            .unwrap();
+        assert_eq!(selection.joltage, 2);
+        assert_eq!(selection.indices, vec![1, 2]);
+
+        let window = Strategy {
+            window: Some(5),
+            ..Strategy::default()
+        };
+        let selection = bank("12345543219").select(2, &window).unwrap();
+        assert_eq!(selection.joltage, 45 + 54);
+        assert_eq!(selection.indices, vec![3, 4, 5, 6]);
+        assert_eq!(
+            bank("123455432198").select(2, &window).map(|s| s.joltage),
+            Ok(45 + 54 + 98)
+        );
+        assert_eq!(bank("1234").select(2, &window).map(|s| s.joltage), Ok(34));
+        assert_eq!(
+            bank("1234554321").select(6, &window),
+            Err(JoltageError::WindowTooSmall { window: 5, k: 6 })
+        );
+        assert_eq!(
+            solve(banks(&["1234554321"]), 6, &window, false, |_, _, _| {}),
+            Err(JoltageError::WindowTooSmall { window: 5, k: 6 })
+        );
+        assert_eq!(
+            bank("1234554321").select(2, &window).map(|s| s.joltage),
+            Ok(45 + 54)
+        );
+    }
+}